@@ -0,0 +1,237 @@
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use std::{
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::{Anomaly, Error, Result},
+    map::{Cell, Layer, Map, TextureKind, KIND_MASK},
+    read::ParseOptions,
+    triggers::TriggerTable,
+};
+
+const MAGIC: &[u8; 4] = b"FMAP";
+const VERSION: u16 = 2;
+
+// Layout of version 2, all integers little endian:
+//
+//   magic "FMAP", u16 version
+//   u16 width, u16 height
+//   base, var and anm paths, each a u16 length followed by UTF-8 bytes
+//   u8 layer count, then per layer `width * height` cells of
+//   u8 index, u8 kind byte, u8 trigger byte
+//   u32 length of the trailing `.cn2` bytes, then the bytes
+//
+// Version 1 has no trailing bytes. Anomalies recorded in lenient mode aren't
+// stored, though the bytes behind them are.
+
+impl Map {
+    /// Writes the map in the compact binary format, encoding triggers with
+    /// `triggers`.
+    pub fn write_compact<W: io::Write>(&self, w: &mut W, triggers: &TriggerTable) -> Result<()> {
+        if self.width > u16::MAX as usize || self.height > u16::MAX as usize {
+            return Err(Error::Encode(format!(
+                "{}x{} doesn't fit a compact map header",
+                self.width, self.height
+            )));
+        }
+
+        if self.trailing.len() > u32::MAX as usize {
+            return Err(Error::Encode(format!(
+                "{} trailing bytes don't fit a compact map",
+                self.trailing.len()
+            )));
+        }
+
+        if self.layers.len() > u8::MAX as usize {
+            return Err(Error::Encode(format!(
+                "{} layers don't fit a compact map",
+                self.layers.len()
+            )));
+        }
+
+        let trigger_bytes = self.trigger_bytes(triggers)?;
+
+        w.write_all(MAGIC)?;
+        w.write_u16::<LittleEndian>(VERSION)?;
+
+        w.write_u16::<LittleEndian>(self.width as u16)?;
+        w.write_u16::<LittleEndian>(self.height as u16)?;
+
+        for path in [&self.base, &self.var, &self.anm].iter() {
+            write_path(w, path)?;
+        }
+
+        w.write_u8(self.layers.len() as u8)?;
+
+        for (idx, (layer, trigger_bytes)) in self.layers.iter().zip(&trigger_bytes).enumerate() {
+            if layer.cells.len() != self.width * self.height {
+                return Err(Error::Encode(format!(
+                    "layer {} has {} cells, expected {}",
+                    idx,
+                    layer.cells.len(),
+                    self.width * self.height
+                )));
+            }
+
+            for (cell, trigger) in layer.cells.iter().zip(trigger_bytes) {
+                w.write_all(&[cell.index, cell.kind_byte(), *trigger])?;
+            }
+        }
+
+        w.write_u32::<LittleEndian>(self.trailing.len() as u32)?;
+        w.write_all(&self.trailing)?;
+
+        Ok(())
+    }
+
+    /// Reads a map written by `write_compact`, decoding triggers with the
+    /// table of `options`. Texture kinds naming no kind are only tolerated in
+    /// lenient mode, as when parsing a `.cn2` file.
+    pub fn read_compact(bytes: &[u8], options: &ParseOptions) -> Result<Map> {
+        let mut r = io::Cursor::new(bytes);
+
+        let mut magic = [0; 4];
+        if r.read_exact(&mut magic).is_err() || &magic != MAGIC {
+            return Err(Error::NotCompact);
+        }
+
+        let version = r.read_u16::<LittleEndian>()?;
+        if version == 0 || version > VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
+        let width = r.read_u16::<LittleEndian>()? as usize;
+        let height = r.read_u16::<LittleEndian>()? as usize;
+
+        let base = read_path(&mut r)?;
+        let var = read_path(&mut r)?;
+        let anm = read_path(&mut r)?;
+
+        let layer_count = r.read_u8()? as usize;
+
+        // The header is only trusted as far as the bytes left can back it
+        let cells_left = |r: &io::Cursor<&[u8]>| (bytes.len() - r.position() as usize) / 3;
+
+        let mut layers = Vec::with_capacity(layer_count.min(cells_left(&r)));
+        let mut anomalies = vec![];
+
+        for layer in 0..layer_count {
+            let mut cells = Vec::with_capacity((width * height).min(cells_left(&r)));
+
+            for cell in 0..width * height {
+                let mut buf = [0; 3];
+                r.read_exact(&mut buf)?;
+
+                let kind = match TextureKind::make(buf[1] & KIND_MASK) {
+                    Some(kind) => kind,
+                    None => {
+                        let anomaly = Anomaly::InvalidTextureKind {
+                            offset: r.position() - 2,
+                            layer,
+                            cell,
+                            value: buf[1],
+                        };
+
+                        if !options.lenient {
+                            return Err(Error::Invalid(anomaly));
+                        }
+
+                        anomalies.push(anomaly);
+                        TextureKind::Base
+                    }
+                };
+
+                cells.push(Cell {
+                    index: buf[0],
+                    kind,
+                    raw_kind: Some(buf[1]).filter(|&value| value != kind.to_byte()),
+                    trigger: options.triggers.decode(buf[2]),
+                    raw_trigger: Some(buf[2]),
+                });
+            }
+
+            layers.push(Layer { cells });
+        }
+
+        let trailing = if version >= 2 {
+            let len = r.read_u32::<LittleEndian>()?;
+
+            let mut buf = vec![];
+            r.by_ref().take(u64::from(len)).read_to_end(&mut buf)?;
+
+            if buf.len() != len as usize {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+
+            buf
+        } else {
+            vec![]
+        };
+
+        Ok(Map {
+            width,
+            height,
+            base,
+            var,
+            anm,
+            layers,
+            trailing,
+            anomalies,
+        })
+    }
+}
+
+fn write_path<W: io::Write>(w: &mut W, path: &Path) -> Result<()> {
+    let s = path
+        .to_str()
+        .ok_or_else(|| Error::Encode(format!("{} isn't valid UTF-8", path.display())))?;
+
+    if s.len() > u16::MAX as usize {
+        return Err(Error::Encode(format!("{} is too long", path.display())));
+    }
+
+    w.write_u16::<LittleEndian>(s.len() as u16)?;
+    w.write_all(s.as_bytes())?;
+
+    Ok(())
+}
+
+fn read_path<R: io::Read>(r: &mut R) -> Result<PathBuf> {
+    let len = r.read_u16::<LittleEndian>()? as usize;
+
+    let mut buf = vec![0; len];
+    r.read_exact(&mut buf)?;
+
+    String::from_utf8(buf)
+        .map(PathBuf::from)
+        .map_err(|e| Error::Serde(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, Map, ParseOptions};
+
+    #[test]
+    fn rejects_unknown_versions() {
+        for version in [0u8, 3].iter() {
+            let bytes = [b'F', b'M', b'A', b'P', *version, 0, 0, 0, 0, 0];
+
+            assert!(matches!(
+                Map::read_compact(&bytes, &ParseOptions::default()),
+                Err(Error::UnsupportedVersion(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn huge_header_fails_without_allocating() {
+        let mut bytes = b"FMAP\x02\x00\xFF\xFF\xFF\xFF".to_vec();
+        bytes.extend_from_slice(&[0; 6]);
+        bytes.push(0xFF);
+
+        assert!(Map::read_compact(&bytes, &ParseOptions::default()).is_err());
+    }
+}
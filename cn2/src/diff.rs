@@ -0,0 +1,157 @@
+use serde::Serialize;
+
+use std::path::PathBuf;
+
+use crate::map::{Cell, Map, TextureKind};
+
+/// Differences between two maps. Cells are compared by coordinates, so only
+/// the area both maps cover is compared when their dimensions differ.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct MapDiff {
+    /// Width and height before and after, if they differ.
+    pub dimensions: Option<[(usize, usize); 2]>,
+    /// Texture paths that changed. A `.cn2` map stores none, so they're only
+    /// compared when both maps have one.
+    pub textures: Vec<TextureChange>,
+    /// Layer counts before and after, if they differ.
+    pub layers: Option<[usize; 2]>,
+    pub cells: Vec<CellChange>,
+    /// Trailing byte counts before and after, if the bytes differ.
+    pub trailing: Option<[usize; 2]>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TextureChange {
+    pub kind: TextureKind,
+    pub before: PathBuf,
+    pub after: PathBuf,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CellChange {
+    pub layer: usize,
+    pub x: usize,
+    pub y: usize,
+    pub before: Cell,
+    pub after: Cell,
+}
+
+impl CellChange {
+    pub fn tile_changed(&self) -> bool {
+        self.before.index != self.after.index || self.before.kind_byte() != self.after.kind_byte()
+    }
+
+    /// Whether the trigger or, where both cells keep it, the raw trigger byte
+    /// changed.
+    pub fn trigger_changed(&self) -> bool {
+        self.before.trigger != self.after.trigger
+            || matches!(
+                (self.before.raw_trigger, self.after.raw_trigger),
+                (Some(before), Some(after)) if before != after
+            )
+    }
+}
+
+impl MapDiff {
+    pub fn is_empty(&self) -> bool {
+        self.dimensions.is_none()
+            && self.textures.is_empty()
+            && self.layers.is_none()
+            && self.cells.is_empty()
+            && self.trailing.is_none()
+    }
+}
+
+impl Map {
+    /// Compares this map, the one before, to `after`.
+    pub fn diff(&self, after: &Map) -> MapDiff {
+        let mut diff = MapDiff::default();
+
+        if (self.width, self.height) != (after.width, after.height) {
+            diff.dimensions = Some([(self.width, self.height), (after.width, after.height)]);
+        }
+
+        for (kind, before, after) in [
+            (TextureKind::Base, &self.base, &after.base),
+            (TextureKind::Var, &self.var, &after.var),
+            (TextureKind::Anm, &self.anm, &after.anm),
+        ]
+        .iter()
+        {
+            let missing = before.as_os_str().is_empty() || after.as_os_str().is_empty();
+
+            if !missing && before != after {
+                diff.textures.push(TextureChange {
+                    kind: *kind,
+                    before: before.to_path_buf(),
+                    after: after.to_path_buf(),
+                });
+            }
+        }
+
+        if self.layers.len() != after.layers.len() {
+            diff.layers = Some([self.layers.len(), after.layers.len()]);
+        }
+
+        for (layer, (before_layer, after_layer)) in
+            self.layers.iter().zip(after.layers.iter()).enumerate()
+        {
+            for y in 0..self.height.min(after.height) {
+                for x in 0..self.width.min(after.width) {
+                    let before = self
+                        .cell_index(x, y)
+                        .and_then(|idx| before_layer.cells.get(idx));
+                    let after_cell = after
+                        .cell_index(x, y)
+                        .and_then(|idx| after_layer.cells.get(idx));
+
+                    if let (Some(before), Some(after_cell)) = (before, after_cell) {
+                        let change = CellChange {
+                            layer,
+                            x,
+                            y,
+                            before: before.clone(),
+                            after: after_cell.clone(),
+                        };
+
+                        if change.tile_changed() || change.trigger_changed() {
+                            diff.cells.push(change);
+                        }
+                    }
+                }
+            }
+        }
+
+        if self.trailing != after.trailing {
+            diff.trailing = Some([self.trailing.len(), after.trailing.len()]);
+        }
+
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::Map;
+
+    #[test]
+    fn ignores_missing_texture_paths() {
+        let cn2 = Map::default();
+        let exported = Map {
+            base: PathBuf::from("tilesets/town/town_base.png"),
+            ..Map::default()
+        };
+
+        assert!(cn2.diff(&exported).is_empty());
+        assert!(exported.diff(&cn2).is_empty());
+
+        let moved = Map {
+            base: PathBuf::from("tilesets/town_base.png"),
+            ..Map::default()
+        };
+
+        assert_eq!(exported.diff(&moved).textures.len(), 1);
+    }
+}
@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+
+use std::{error, fmt, io, path::PathBuf, result};
+
+pub type Result<T> = result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// The file contents are truncated or invalid.
+    Invalid(Anomaly),
+    /// The `_base`, `_var` and `_anm` textures of a map couldn't be found.
+    MissingTextures(PathBuf),
+    /// The map can't be encoded in the requested format.
+    Encode(String),
+    /// The file extension isn't one of the supported map formats.
+    UnknownFormat(PathBuf),
+    /// RON or JSON (de)serialization failed.
+    Serde(String),
+    /// The data doesn't start with the compact map header.
+    NotCompact,
+    /// The compact map was written by a newer version of this crate.
+    UnsupportedVersion(u16),
+    /// A texture couldn't be opened or decoded.
+    Texture { path: PathBuf, reason: String },
+    /// A Tiled map doesn't fit the constraints of a `.cn2` map.
+    Tiled(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Invalid(anomaly) => write!(f, "{}", anomaly),
+            Error::MissingTextures(path) => write!(
+                f,
+                "Couldn't find all related _base, _var and _anm textures for {}",
+                path.display()
+            ),
+            Error::Encode(reason) => write!(f, "Couldn't encode map: {}", reason),
+            Error::UnknownFormat(path) => {
+                write!(f, "{} isn't a supported map format", path.display())
+            }
+            Error::Serde(reason) => write!(f, "{}", reason),
+            Error::NotCompact => write!(f, "Missing compact map header"),
+            Error::UnsupportedVersion(version) => {
+                write!(f, "Unsupported compact map version {}", version)
+            }
+            Error::Texture { path, reason } => {
+                write!(f, "Couldn't load texture {}: {}", path.display(), reason)
+            }
+            Error::Tiled(reason) => write!(f, "Can't import Tiled map: {}", reason),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+/// A region of a `.cn2` file.
+#[derive(Debug, Copy, Clone, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Section {
+    Header,
+    Layer(usize),
+    Triggers,
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Section::Header => write!(f, "header"),
+            Section::Layer(layer) => write!(f, "layer {}", layer),
+            Section::Triggers => write!(f, "trigger block"),
+        }
+    }
+}
+
+/// A problem with the contents of a map file.
+#[derive(Debug, Clone, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Anomaly {
+    /// The file ended `found` bytes into a section that should be `expected`
+    /// bytes long. `offset` is where the data ran out.
+    Truncated {
+        section: Section,
+        offset: u64,
+        expected: usize,
+        found: usize,
+    },
+    /// A cell's texture kind byte isn't one of `Base`, `Var` or `Anm`.
+    InvalidTextureKind {
+        offset: u64,
+        layer: usize,
+        cell: usize,
+        value: u8,
+    },
+    /// An exported map is zero cells wide or high.
+    EmptyMap { width: usize, height: usize },
+    /// A layer of an exported map doesn't hold `width * height` cells.
+    LayerSize {
+        layer: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Anomaly::Truncated {
+                section,
+                offset,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} truncated at offset {:#x}: expected {} bytes, found {}",
+                section, offset, expected, found
+            ),
+            Anomaly::InvalidTextureKind {
+                offset,
+                layer,
+                cell,
+                value,
+            } => write!(
+                f,
+                "invalid texture kind {:#04x} at offset {:#x} (layer {}, cell {})",
+                value, offset, layer, cell
+            ),
+            Anomaly::EmptyMap { width, height } => {
+                write!(
+                    f,
+                    "map is {}x{} cells, expected at least 1x1",
+                    width, height
+                )
+            }
+            Anomaly::LayerSize {
+                layer,
+                expected,
+                found,
+            } => write!(
+                f,
+                "layer {} has {} cells, expected {}",
+                layer, found, expected
+            ),
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use ron::ser::{to_string_pretty, PrettyConfig};
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    error::{Anomaly, Error, Result},
+    map::Map,
+    path::PathExt,
+    read::ParseOptions,
+    textures::Textures,
+    triggers::TriggerTable,
+};
+
+/// The file formats a `Map` can be stored in.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MapFormat {
+    /// The game's own `.cn2` layout. Texture paths aren't stored.
+    Cn2,
+    Ron,
+    Json,
+    /// The versioned binary format written by `Map::write_compact`.
+    Bin,
+}
+
+impl MapFormat {
+    pub fn from_path(path: impl AsRef<Path>) -> Option<MapFormat> {
+        path.ext_str().parse().ok()
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            MapFormat::Cn2 => "cn2",
+            MapFormat::Ron => "ron",
+            MapFormat::Json => "json",
+            MapFormat::Bin => "bin",
+        }
+    }
+}
+
+impl FromStr for MapFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "cn2" => Ok(MapFormat::Cn2),
+            "ron" => Ok(MapFormat::Ron),
+            "json" => Ok(MapFormat::Json),
+            "bin" => Ok(MapFormat::Bin),
+            _ => Err(format!("Unknown map format `{}`", s)),
+        }
+    }
+}
+
+impl fmt::Display for MapFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.extension())
+    }
+}
+
+impl Map {
+    /// Reads a map in whichever format its file extension names. `.cn2` files
+    /// have their textures discovered next to them.
+    pub fn open(path: impl AsRef<Path>, options: &ParseOptions) -> Result<Map> {
+        let path = path.as_ref();
+
+        match MapFormat::from_path(path) {
+            Some(MapFormat::Cn2) => Map::load(path, options),
+            Some(format) => Map::decode(&fs::read(path)?, format, options),
+            None => Err(Error::UnknownFormat(path.to_path_buf())),
+        }
+    }
+
+    /// Reads a map like `open`, but without looking for the textures of a
+    /// `.cn2` map, for tools that only need its cells.
+    pub fn open_without_textures(path: impl AsRef<Path>, options: &ParseOptions) -> Result<Map> {
+        let path = path.as_ref();
+        let format =
+            MapFormat::from_path(path).ok_or_else(|| Error::UnknownFormat(path.to_path_buf()))?;
+
+        Map::decode(&fs::read(path)?, format, options)
+    }
+
+    /// Writes the map in whichever format the file extension of `path` names.
+    pub fn save(&self, path: impl AsRef<Path>, triggers: &TriggerTable) -> Result<()> {
+        let path = path.as_ref();
+
+        let format =
+            MapFormat::from_path(path).ok_or_else(|| Error::UnknownFormat(PathBuf::from(path)))?;
+
+        fs::write(path, self.encode(format, triggers)?)?;
+
+        Ok(())
+    }
+
+    /// Decodes a map from `bytes`. A `.cn2` map decoded this way has no
+    /// texture paths. `options` apply to the binary formats, the others store
+    /// triggers by name and have their dimensions checked against the layers.
+    pub fn decode(bytes: &[u8], format: MapFormat, options: &ParseOptions) -> Result<Map> {
+        let map: Map = match format {
+            MapFormat::Cn2 => return Map::parse(bytes, "", &Textures::default(), options),
+            MapFormat::Ron => {
+                ron::de::from_bytes(bytes).map_err(|e| Error::Serde(e.to_string()))?
+            }
+            MapFormat::Json => {
+                serde_json::from_slice(bytes).map_err(|e| Error::Serde(e.to_string()))?
+            }
+            MapFormat::Bin => return Map::read_compact(bytes, options),
+        };
+
+        map.check_dimensions()?;
+
+        Ok(map)
+    }
+
+    /// Checks the map is at least one cell wide and high, and every layer has
+    /// a cell for each position, which indexing cells by position relies on.
+    fn check_dimensions(&self) -> Result<()> {
+        if self.width == 0 || self.height == 0 {
+            return Err(Error::Invalid(Anomaly::EmptyMap {
+                width: self.width,
+                height: self.height,
+            }));
+        }
+
+        let expected = self.width.saturating_mul(self.height);
+
+        for (layer, cells) in self.layers.iter().enumerate() {
+            if cells.cells.len() != expected {
+                return Err(Error::Invalid(Anomaly::LayerSize {
+                    layer,
+                    expected,
+                    found: cells.cells.len(),
+                }));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Encodes the map in `format`. The binary formats encode triggers with
+    /// `triggers`, the others store them by name.
+    pub fn encode(&self, format: MapFormat, triggers: &TriggerTable) -> Result<Vec<u8>> {
+        match format {
+            MapFormat::Cn2 => self.to_bytes(triggers),
+            MapFormat::Ron => {
+                let pretty = PrettyConfig {
+                    depth_limit: 6,
+                    separate_tuple_members: true,
+                    enumerate_arrays: true,
+                    ..Default::default()
+                };
+
+                to_string_pretty(self, pretty)
+                    .map(String::into_bytes)
+                    .map_err(|e| Error::Serde(e.to_string()))
+            }
+            MapFormat::Json => {
+                serde_json::to_vec_pretty(self).map_err(|e| Error::Serde(e.to_string()))
+            }
+            MapFormat::Bin => {
+                let mut buf = vec![];
+                self.write_compact(&mut buf, triggers)?;
+
+                Ok(buf)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Anomaly, Cell, Error, Layer, Map, MapFormat, ParseOptions, TriggerTable};
+
+    fn decode_ron(map: &Map) -> Result<Map, Error> {
+        let bytes = map
+            .encode(MapFormat::Ron, &TriggerTable::default())
+            .unwrap();
+
+        Map::decode(&bytes, MapFormat::Ron, &ParseOptions::default())
+    }
+
+    fn map(width: usize, height: usize, cells: usize) -> Map {
+        Map {
+            width,
+            height,
+            layers: vec![Layer {
+                cells: vec![Cell::default(); cells],
+            }],
+            ..Map::default()
+        }
+    }
+
+    #[test]
+    fn decodes_consistent_dimensions() {
+        assert!(decode_ron(&map(2, 3, 6)).is_ok());
+    }
+
+    #[test]
+    fn rejects_empty_maps() {
+        match decode_ron(&map(0, 1, 1)) {
+            Err(Error::Invalid(Anomaly::EmptyMap { width, height })) => {
+                assert_eq!((width, height), (0, 1));
+            }
+            other => panic!("expected an empty map error, got {:?}", other),
+        }
+
+        assert!(decode_ron(&map(1, 0, 0)).is_err());
+    }
+
+    #[test]
+    fn rejects_layers_of_the_wrong_size() {
+        let json = serde_json::to_vec(&map(2, 2, 3)).unwrap();
+
+        match Map::decode(&json, MapFormat::Json, &ParseOptions::default()) {
+            Err(Error::Invalid(Anomaly::LayerSize {
+                layer,
+                expected,
+                found,
+            })) => assert_eq!((layer, expected, found), (0, 4, 3)),
+            other => panic!("expected a layer size error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::{Error, Result},
+    format::MapFormat,
+};
+
+/// The maps a tool was pointed at: `path` itself if it's a file, otherwise
+/// the files in the directory with a map extension, sorted. Subdirectories
+/// are searched if `recursive`, symlinks aren't followed.
+pub fn inputs(path: &Path, recursive: bool) -> Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    } else if !path.is_dir() {
+        return Err(Error::Io(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} isn't a file or directory", path.display()),
+        )));
+    }
+
+    let mut inputs = vec![];
+    collect(path, recursive, &mut inputs)?;
+
+    inputs.sort();
+
+    Ok(inputs)
+}
+
+fn collect(dir: &Path, recursive: bool, inputs: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() && recursive {
+            collect(&entry.path(), recursive, inputs)?;
+        } else if file_type.is_file() && MapFormat::from_path(entry.path()).is_some() {
+            inputs.push(entry.path());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf};
+
+    use super::inputs;
+
+    #[test]
+    fn finds_maps_by_extension() {
+        let dir = env::temp_dir().join(format!("cn2-inputs-{}", std::process::id()));
+        let nested = dir.join("nested");
+        fs::create_dir_all(&nested).unwrap();
+
+        for name in ["b.ron", "a.cn2", "notes.txt"].iter() {
+            fs::write(dir.join(name), "").unwrap();
+        }
+        fs::write(nested.join("c.bin"), "").unwrap();
+
+        let names = |recursive| {
+            inputs(&dir, recursive)
+                .unwrap()
+                .iter()
+                .map(|path| path.strip_prefix(&dir).unwrap().to_path_buf())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(false),
+            vec![PathBuf::from("a.cn2"), PathBuf::from("b.ron")]
+        );
+        assert_eq!(
+            names(true),
+            vec![
+                PathBuf::from("a.cn2"),
+                PathBuf::from("b.ron"),
+                PathBuf::from("nested/c.bin")
+            ]
+        );
+        assert_eq!(
+            inputs(&dir.join("b.ron"), false).unwrap(),
+            vec![dir.join("b.ron")]
+        );
+        assert!(inputs(&dir.join("missing"), false).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,75 @@
+use byteorder::{ByteOrder, LittleEndian};
+
+use std::ops::Range;
+
+/// Byte ranges of the sections of a `.cn2` file with the given dimensions.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Layout {
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Size of the u16 width and height header.
+pub const HEADER_LEN: usize = 4;
+
+impl Layout {
+    pub fn new(width: usize, height: usize) -> Layout {
+        Layout { width, height }
+    }
+
+    /// The layout described by the header of `bytes`, `None` if it's shorter
+    /// than a header.
+    pub fn from_header(bytes: &[u8]) -> Option<Layout> {
+        if bytes.len() < HEADER_LEN {
+            return None;
+        }
+
+        Some(Layout::new(
+            LittleEndian::read_u16(&bytes[0..2]) as _,
+            LittleEndian::read_u16(&bytes[2..4]) as _,
+        ))
+    }
+
+    pub fn cell_count(&self) -> usize {
+        self.width * self.height
+    }
+
+    pub fn header(&self) -> Range<usize> {
+        0..HEADER_LEN
+    }
+
+    /// The index / kind pairs of `layer`.
+    pub fn layer(&self, layer: usize) -> Range<usize> {
+        let start = HEADER_LEN + layer * self.cell_count() * 2;
+
+        start..start + self.cell_count() * 2
+    }
+
+    /// The trigger table of `layer`.
+    pub fn triggers(&self, layer: usize) -> Range<usize> {
+        let start = self.layer(2).start + layer * self.cell_count();
+
+        start..start + self.cell_count()
+    }
+
+    /// Length of a file with this layout, anything after it is trailing data.
+    pub fn file_len(&self) -> usize {
+        self.triggers(1).end
+    }
+
+    /// Offsets of the index, kind and trigger bytes of cell `idx` in `layer`.
+    pub fn cell(&self, layer: usize, idx: usize) -> (usize, usize, usize) {
+        let index = self.layer(layer).start + idx * 2;
+
+        (index, index + 1, self.triggers(layer).start + idx)
+    }
+
+    /// The cell index of `(x, y)`, `None` if it's outside the map.
+    pub fn cell_at(&self, x: usize, y: usize) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some(y * self.width + x)
+        } else {
+            None
+        }
+    }
+}
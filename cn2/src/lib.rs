@@ -0,0 +1,41 @@
+//! Data model, parser and encoder for the `.cn2` tileset metadata files of
+//! Final Fantasy IV: The After Years.
+//!
+//! A `.cn2` file is laid out as a u16 width and height, two layers of
+//! index / texture kind pairs, then a trigger table for each layer.
+
+mod compact;
+mod diff;
+mod error;
+mod format;
+mod inputs;
+mod layout;
+mod map;
+mod path;
+mod query;
+mod read;
+#[cfg(feature = "render")]
+pub mod render;
+mod stats;
+mod textures;
+pub mod tiled;
+mod triggers;
+mod validate;
+mod write;
+
+pub use diff::{CellChange, MapDiff, TextureChange};
+pub use error::{Anomaly, Error, Result, Section};
+pub use format::MapFormat;
+pub use inputs::inputs;
+pub use layout::{Layout, HEADER_LEN};
+pub use map::{
+    Cell, Layer, Map, TextureKind, TriggerKind, ANM_FRAMES, ANM_FRAME_MS, SHEET_COLUMNS,
+    SHEET_TILES, TILE_SIZE,
+};
+pub use path::{relative_path, PathExt};
+pub use query::{Located, Region, Tile};
+pub use read::ParseOptions;
+pub use stats::{Stats, TileUsage};
+pub use textures::{find_textures, DirResolver, TextureResolver, Textures};
+pub use triggers::{TriggerRule, TriggerTable};
+pub use validate::Problem;
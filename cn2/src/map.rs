@@ -0,0 +1,233 @@
+use serde::{Deserialize, Serialize};
+
+use std::{path::PathBuf, str::FromStr};
+
+use crate::{error::Anomaly, triggers::TriggerTable};
+
+/// Width and height of a tile in pixels.
+pub const TILE_SIZE: u32 = 32;
+
+/// Tiles per row of a sprite sheet.
+pub const SHEET_COLUMNS: u32 = 16;
+
+/// Tiles in a sprite sheet.
+pub const SHEET_TILES: u32 = 256;
+
+/// Number of frames an `Anm` tile cycles through, starting at its index.
+pub const ANM_FRAMES: u8 = 4;
+
+/// How long each frame of an `Anm` tile is shown, in milliseconds.
+pub const ANM_FRAME_MS: u32 = 250;
+
+/// Bits of a kind byte holding the `TextureKind`.
+pub const KIND_MASK: u8 = 0b11;
+
+#[derive(Debug, Default, Copy, Clone, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum TextureKind {
+    #[default]
+    Base,
+    Var,
+    Anm,
+}
+
+impl TextureKind {
+    pub fn make(value: u8) -> Option<TextureKind> {
+        match value {
+            0 => Some(TextureKind::Base),
+            1 => Some(TextureKind::Var),
+            2 => Some(TextureKind::Anm),
+            _ => None,
+        }
+    }
+
+    /// Splits a kind byte into the texture kind in its low bits, `None` if
+    /// they don't name one, and the remaining high bits.
+    pub fn split(value: u8) -> (Option<TextureKind>, u8) {
+        (TextureKind::make(value & KIND_MASK), value & !KIND_MASK)
+    }
+
+    pub fn to_byte(self) -> u8 {
+        match self {
+            TextureKind::Base => 0,
+            TextureKind::Var => 1,
+            TextureKind::Anm => 2,
+        }
+    }
+}
+
+impl FromStr for TextureKind {
+    type Err = String;
+
+    /// Parses `base`, `var` or `anm`, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "base" => Ok(TextureKind::Base),
+            "var" => Ok(TextureKind::Var),
+            "anm" => Ok(TextureKind::Anm),
+            _ => Err(format!(
+                "Expected `base`, `var` or `anm`, found `{}`",
+                s.trim()
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum TriggerKind {
+    #[default]
+    Uninitialized,
+    Passable,
+    Blocker,
+    UpperLowerDelta,
+    LowerUpperDelta,
+    Hidden,
+    Bridge,
+    Damage,
+    BottomTransparent,
+    BottomHidden,
+    Unknown7,
+    Unknown12,
+    Unknown13,
+    Treasure(u8),
+    Exit(u8),
+    Unknown(u8),
+}
+
+impl TriggerKind {
+    /// The kind a `TriggerTable` rule names, with `param` as the id of
+    /// `Treasure`, `Exit` and `Unknown`.
+    pub fn from_name(name: &str, param: u8) -> Option<TriggerKind> {
+        Some(match name {
+            "Uninitialized" => TriggerKind::Uninitialized,
+            "Passable" => TriggerKind::Passable,
+            "Blocker" => TriggerKind::Blocker,
+            "UpperLowerDelta" => TriggerKind::UpperLowerDelta,
+            "LowerUpperDelta" => TriggerKind::LowerUpperDelta,
+            "Hidden" => TriggerKind::Hidden,
+            "Bridge" => TriggerKind::Bridge,
+            "Damage" => TriggerKind::Damage,
+            "BottomTransparent" => TriggerKind::BottomTransparent,
+            "BottomHidden" => TriggerKind::BottomHidden,
+            "Unknown7" => TriggerKind::Unknown7,
+            "Unknown12" => TriggerKind::Unknown12,
+            "Unknown13" => TriggerKind::Unknown13,
+            "Treasure" => TriggerKind::Treasure(param),
+            "Exit" => TriggerKind::Exit(param),
+            "Unknown" => TriggerKind::Unknown(param),
+            _ => return None,
+        })
+    }
+
+    /// The variant name, without the id of `Treasure`, `Exit` and `Unknown`.
+    pub fn name(self) -> String {
+        let name = format!("{:?}", self);
+
+        match name.find('(') {
+            Some(end) => name[..end].to_string(),
+            None => name,
+        }
+    }
+}
+
+impl FromStr for TriggerKind {
+    type Err = String;
+
+    /// Parses a variant as it's written in RON, e.g. `Blocker` or `Exit(3)`.
+    /// Ids may be decimal or `0x` prefixed hex.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        let (name, param) = match s.find('(') {
+            Some(open) if s.ends_with(')') => {
+                let param = s[open + 1..s.len() - 1].trim();
+
+                let value = if param.starts_with("0x") || param.starts_with("0X") {
+                    u8::from_str_radix(&param[2..], 16)
+                } else {
+                    param.parse::<u8>()
+                };
+
+                let value = value.map_err(|e| format!("Invalid trigger id `{}`: {}", param, e))?;
+
+                (s[..open].trim(), Some(value))
+            }
+            _ => (s, None),
+        };
+
+        let kind = TriggerKind::from_name(name, param.unwrap_or(0))
+            .ok_or_else(|| format!("Unknown trigger `{}`", name))?;
+
+        let has_id = matches!(
+            kind,
+            TriggerKind::Treasure(_) | TriggerKind::Exit(_) | TriggerKind::Unknown(_)
+        );
+
+        match (has_id, param) {
+            (true, None) => Err(format!("`{}` needs an id, e.g. `{}(3)`", name, name)),
+            (false, Some(_)) => Err(format!("`{}` doesn't take an id", name)),
+            _ => Ok(kind),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Cell {
+    pub index: u8,
+    pub kind: TextureKind,
+    /// The kind byte `kind` was decoded from, if it holds more than `kind`:
+    /// bits above `KIND_MASK` whose meaning is unknown, or low bits naming no
+    /// kind in lenient mode. It's written back as long as it still decodes to
+    /// `kind`, otherwise only its unknown bits are kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_kind: Option<u8>,
+    pub trigger: TriggerKind,
+    /// The trigger byte `trigger` was decoded from. It's written back as long
+    /// as it still decodes to `trigger`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_trigger: Option<u8>,
+}
+
+impl Cell {
+    /// The kind byte to write for this cell.
+    pub fn kind_byte(&self) -> u8 {
+        match self.raw_kind {
+            // Lenient parsing reads low bits naming no kind as `Base`
+            Some(raw) if TextureKind::make(raw & KIND_MASK).unwrap_or_default() == self.kind => raw,
+            Some(raw) => self.kind.to_byte() | (raw & !KIND_MASK),
+            None => self.kind.to_byte(),
+        }
+    }
+
+    /// The trigger byte to write for this cell, `raw_trigger` unless it no
+    /// longer decodes to `trigger` with `triggers`. `None` if `trigger` has
+    /// no byte in the table.
+    pub fn trigger_byte(&self, triggers: &TriggerTable) -> Option<u8> {
+        match self.raw_trigger {
+            Some(raw) if triggers.decode(raw) == self.trigger => Some(raw),
+            _ => triggers.encode(self.trigger),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Layer {
+    pub cells: Vec<Cell>,
+}
+
+/// A map parsed from a `.cn2` file, along with the textures its cells index
+/// into.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Map {
+    pub width: usize,
+    pub height: usize,
+    pub base: PathBuf,
+    pub var: PathBuf,
+    pub anm: PathBuf,
+    pub layers: Vec<Layer>,
+    /// Bytes after the trigger block, which the parser doesn't understand.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trailing: Vec<u8>,
+    /// Problems tolerated while parsing in lenient mode.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub anomalies: Vec<Anomaly>,
+}
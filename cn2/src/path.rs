@@ -0,0 +1,69 @@
+use std::path::{Path, PathBuf};
+
+pub trait PathExt {
+    fn file_name_str(&self) -> &str;
+
+    fn file_stem_str(&self) -> &str;
+
+    fn ext_str(&self) -> &str;
+}
+
+impl<P: AsRef<Path>> PathExt for P {
+    fn file_name_str(&self) -> &str {
+        self.as_ref()
+            .file_name()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default()
+    }
+
+    fn file_stem_str(&self) -> &str {
+        self.as_ref()
+            .file_stem()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default()
+    }
+
+    fn ext_str(&self) -> &str {
+        self.as_ref()
+            .extension()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default()
+    }
+}
+
+/// Returns `path` relative to `base`, stepping out of `base` with `..` where
+/// needed. Both paths should be absolute, or relative to the same directory.
+/// `None` if they don't share a root, e.g. are on different drives.
+pub fn relative_path(path: &Path, base: &Path) -> Option<PathBuf> {
+    if path.has_root() != base.has_root() {
+        return None;
+    }
+
+    let path_components = path.components().collect::<Vec<_>>();
+    let base_components = base.components().collect::<Vec<_>>();
+
+    let common = path_components
+        .iter()
+        .zip(base_components.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    if common == 0 && path.has_root() {
+        return None;
+    }
+
+    let mut relative = PathBuf::new();
+
+    for _ in common..base_components.len() {
+        relative.push("..");
+    }
+
+    for component in path_components[common..].iter() {
+        relative.push(component);
+    }
+
+    Some(relative)
+}
@@ -0,0 +1,150 @@
+use serde::Serialize;
+
+use std::{result, str::FromStr};
+
+use crate::{
+    layout::Layout,
+    map::{Cell, Map, TextureKind},
+};
+
+/// A tile of one of the sprite sheets, written `kind:index` such as `var:65`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize)]
+pub struct Tile {
+    pub kind: TextureKind,
+    pub index: u8,
+}
+
+impl Tile {
+    pub fn matches(self, cell: &Cell) -> bool {
+        cell.kind == self.kind && cell.index == self.index
+    }
+}
+
+impl FromStr for Tile {
+    type Err = String;
+
+    fn from_str(s: &str) -> result::Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, ':');
+
+        match (parts.next(), parts.next()) {
+            (Some(kind), Some(index)) => Ok(Tile {
+                kind: kind.parse()?,
+                index: index
+                    .trim()
+                    .parse()
+                    .map_err(|e| format!("Invalid tile index `{}`: {}", index.trim(), e))?,
+            }),
+            _ => Err(format!("Expected `kind:index`, found `{}`", s)),
+        }
+    }
+}
+
+/// An inclusive rectangle of cells.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize)]
+pub struct Region {
+    pub x0: usize,
+    pub y0: usize,
+    pub x1: usize,
+    pub y1: usize,
+}
+
+impl Region {
+    pub fn contains(self, x: usize, y: usize) -> bool {
+        (self.x0..=self.x1).contains(&x) && (self.y0..=self.y1).contains(&y)
+    }
+}
+
+impl FromStr for Region {
+    type Err = String;
+
+    /// Parses `x,y` or `x0,y0,x1,y1`.
+    fn from_str(s: &str) -> result::Result<Self, Self::Err> {
+        let values = s
+            .split(',')
+            .map(|v| v.trim().parse::<usize>())
+            .collect::<result::Result<Vec<_>, _>>()
+            .map_err(|e| format!("Invalid coordinates `{}`: {}", s, e))?;
+
+        match values.as_slice() {
+            [x, y] => Ok(Region {
+                x0: *x,
+                y0: *y,
+                x1: *x,
+                y1: *y,
+            }),
+            [x0, y0, x1, y1] => Ok(Region {
+                x0: *x0.min(x1),
+                y0: *y0.min(y1),
+                x1: *x0.max(x1),
+                y1: *y0.max(y1),
+            }),
+            _ => Err(format!("Expected `x,y` or `x0,y0,x1,y1`, found `{}`", s)),
+        }
+    }
+}
+
+/// A cell of a layer along with its coordinates.
+#[derive(Clone, Debug, Serialize)]
+pub struct Located {
+    pub layer: usize,
+    pub x: usize,
+    pub y: usize,
+    pub cell: Cell,
+}
+
+impl Map {
+    /// Coordinates of cell `idx` of a layer.
+    pub fn position(&self, idx: usize) -> (usize, usize) {
+        (idx % self.width, idx / self.width)
+    }
+
+    /// Index into the cells of a layer of the cell at `(x, y)`, if it's inside
+    /// the map.
+    pub fn cell_index(&self, x: usize, y: usize) -> Option<usize> {
+        Layout::new(self.width, self.height).cell_at(x, y)
+    }
+
+    /// The cell of every layer at `(x, y)`, empty if it's outside the map.
+    pub fn cells_at(&self, x: usize, y: usize) -> Vec<Located> {
+        let idx = match self.cell_index(x, y) {
+            Some(idx) => idx,
+            None => return vec![],
+        };
+
+        self.layers
+            .iter()
+            .enumerate()
+            .filter_map(|(layer, cells)| {
+                cells.cells.get(idx).map(|cell| Located {
+                    layer,
+                    x,
+                    y,
+                    cell: cell.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Every cell of every layer that `predicate` accepts given the cell and
+    /// its coordinates, layer by layer in row order.
+    pub fn find(&self, predicate: impl Fn(&Cell, usize, usize) -> bool) -> Vec<Located> {
+        let mut found = vec![];
+
+        for (layer, cells) in self.layers.iter().enumerate() {
+            for (idx, cell) in cells.cells.iter().enumerate() {
+                let (x, y) = self.position(idx);
+
+                if predicate(cell, x, y) {
+                    found.push(Located {
+                        layer,
+                        x,
+                        y,
+                        cell: cell.clone(),
+                    });
+                }
+            }
+        }
+
+        found
+    }
+}
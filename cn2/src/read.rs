@@ -0,0 +1,305 @@
+use byteorder::{ByteOrder, LittleEndian};
+
+use std::{
+    fs,
+    io::{self, Read},
+    path::Path,
+};
+
+use crate::{
+    error::{Anomaly, Error, Result, Section},
+    map::{Cell, Layer, Map, TextureKind, TriggerKind, KIND_MASK},
+    textures::{DirResolver, TextureResolver},
+    triggers::TriggerTable,
+};
+
+#[derive(Clone, Debug, Default)]
+pub struct ParseOptions {
+    /// Record truncated or invalid data in `Map::anomalies` instead of failing.
+    /// Missing cells are zeroed, and a map whose first layer is cut short keeps
+    /// only the rows it covers.
+    pub lenient: bool,
+    /// Decodes the trigger bytes.
+    pub triggers: TriggerTable,
+}
+
+impl Map {
+    /// Parses a `.cn2` file, finding its textures in the same directory.
+    pub fn load(path: impl AsRef<Path>, options: &ParseOptions) -> Result<Map> {
+        let meta = fs::File::open(&path)?;
+
+        Map::read(
+            io::BufReader::new(meta),
+            path,
+            &DirResolver::default(),
+            options,
+        )
+    }
+
+    /// Parses `.cn2` data held in memory. `name` is the name of the file it
+    /// came from, which `resolver` finds the textures by.
+    pub fn parse(
+        bytes: &[u8],
+        name: impl AsRef<Path>,
+        resolver: &impl TextureResolver,
+        options: &ParseOptions,
+    ) -> Result<Map> {
+        Map::read(bytes, name, resolver, options)
+    }
+
+    /// Parses `.cn2` data from `meta`, see `parse`. Reads to the end, as any
+    /// bytes after the trigger block are kept.
+    pub fn read<R: io::Read>(
+        meta: R,
+        name: impl AsRef<Path>,
+        resolver: &impl TextureResolver,
+        options: &ParseOptions,
+    ) -> Result<Map> {
+        let textures = resolver.resolve(name.as_ref())?;
+
+        let mut parser = Parser::new(meta, options.lenient);
+
+        let (width, mut height) = parser.read_header()?;
+
+        let mut layers = parser.build_layers(width, &mut height)?;
+
+        parser.add_triggers(&mut layers, width, height, &options.triggers)?;
+
+        let trailing = parser.read_trailing()?;
+
+        Ok(Map {
+            width,
+            height,
+            base: textures.base,
+            var: textures.var,
+            anm: textures.anm,
+            layers,
+            trailing,
+            anomalies: parser.anomalies,
+        })
+    }
+}
+
+/// Reads the sections of a `.cn2` file, keeping track of the byte offset.
+struct Parser<R> {
+    meta: R,
+    offset: u64,
+    lenient: bool,
+    anomalies: Vec<Anomaly>,
+}
+
+impl<R: io::Read> Parser<R> {
+    fn new(meta: R, lenient: bool) -> Self {
+        Parser {
+            meta,
+            offset: 0,
+            lenient,
+            anomalies: vec![],
+        }
+    }
+
+    /// Fails with `anomaly`, or records it and carries on in lenient mode.
+    fn report(&mut self, anomaly: Anomaly) -> Result<()> {
+        if self.lenient {
+            self.anomalies.push(anomaly);
+            Ok(())
+        } else {
+            Err(Error::Invalid(anomaly))
+        }
+    }
+
+    /// Reads up to `len` bytes. Only as much as the input holds is allocated,
+    /// however large a corrupt header makes `len`.
+    fn fill(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        let mut buf = vec![];
+        let found = self.meta.by_ref().take(len as u64).read_to_end(&mut buf)?;
+
+        self.offset += found as u64;
+
+        Ok(buf)
+    }
+
+    /// Reads `len` bytes of `section`, fewer if the truncation is tolerated.
+    fn read_section(&mut self, section: Section, len: usize) -> Result<Vec<u8>> {
+        let buf = self.fill(len)?;
+
+        if buf.len() < len {
+            self.report(Anomaly::Truncated {
+                section,
+                offset: self.offset,
+                expected: len,
+                found: buf.len(),
+            })?;
+        }
+
+        Ok(buf)
+    }
+
+    fn read_header(&mut self) -> Result<(usize, usize)> {
+        let buf = self.fill(4)?;
+
+        // Without the dimensions there is nothing to recover, even when lenient
+        if buf.len() < 4 {
+            return Err(Error::Invalid(Anomaly::Truncated {
+                section: Section::Header,
+                offset: self.offset,
+                expected: 4,
+                found: buf.len(),
+            }));
+        }
+
+        Ok((
+            LittleEndian::read_u16(&buf[0..2]) as _,
+            LittleEndian::read_u16(&buf[2..4]) as _,
+        ))
+    }
+
+    /// Reads both layers. Missing cells are zeroed, except that a truncated
+    /// first layer cuts `height` to the rows it covers, as the header can't be
+    /// trusted with more.
+    fn build_layers(&mut self, width: usize, height: &mut usize) -> Result<Vec<Layer>> {
+        let mut layers = Vec::with_capacity(2);
+
+        for layer in 0..2 {
+            let start = self.offset;
+            let buf = self.read_section(Section::Layer(layer), width * *height * 2)?;
+
+            if layer == 0 && buf.len() < width * *height * 2 {
+                let found = buf.len() / 2;
+                *height = found / width + usize::from(found % width != 0);
+            }
+
+            let cell_count = width * *height;
+            let mut cells = Vec::with_capacity(cell_count);
+
+            for i in 0..cell_count {
+                let value = buf.get(i * 2 + 1).copied().unwrap_or(0);
+                let kind = match TextureKind::make(value & KIND_MASK) {
+                    Some(kind) => kind,
+                    None => {
+                        self.report(Anomaly::InvalidTextureKind {
+                            offset: start + (i * 2 + 1) as u64,
+                            layer,
+                            cell: i,
+                            value,
+                        })?;
+                        TextureKind::Base
+                    }
+                };
+
+                cells.push(Cell {
+                    index: buf.get(i * 2).copied().unwrap_or(0),
+                    kind,
+                    raw_kind: Some(value).filter(|&value| value != kind.to_byte()),
+                    trigger: TriggerKind::Uninitialized,
+                    raw_trigger: None,
+                });
+            }
+
+            layers.push(Layer { cells });
+        }
+
+        Ok(layers)
+    }
+
+    /// The trigger block holds one table of `cell_count` bytes per layer,
+    /// lower layer first.
+    fn add_triggers(
+        &mut self,
+        layers: &mut [Layer],
+        width: usize,
+        height: usize,
+        triggers: &TriggerTable,
+    ) -> Result<()> {
+        let cell_count = width * height;
+
+        let buf = self.read_section(Section::Triggers, cell_count * 2)?;
+
+        for (idx, layer) in layers.iter_mut().enumerate() {
+            for (i, cell) in layer.cells.iter_mut().enumerate() {
+                let value = buf.get(idx * cell_count + i).copied().unwrap_or(0);
+
+                cell.trigger = triggers.decode(value);
+                cell.raw_trigger = Some(value);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whatever follows the trigger block.
+    fn read_trailing(&mut self) -> Result<Vec<u8>> {
+        let mut buf = vec![];
+        let found = self.meta.read_to_end(&mut buf)?;
+
+        self.offset += found as u64;
+
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Anomaly, Error, Map, ParseOptions, Section, Textures};
+
+    fn parse(bytes: &[u8], lenient: bool) -> crate::Result<Map> {
+        let options = ParseOptions {
+            lenient,
+            ..ParseOptions::default()
+        };
+
+        Map::parse(bytes, "", &Textures::default(), &options)
+    }
+
+    #[test]
+    fn reports_truncation_offset() {
+        let bytes = [0x02, 0x00, 0x01, 0x00, 0x01, 0x00, 0x02];
+
+        match parse(&bytes, false) {
+            Err(Error::Invalid(Anomaly::Truncated {
+                section,
+                offset,
+                expected,
+                found,
+            })) => {
+                assert_eq!(section, Section::Layer(0));
+                assert_eq!((offset, expected, found), (7, 4, 3));
+            }
+            other => panic!("expected a truncated layer, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn lenient_keeps_rows_of_truncated_layer() {
+        let bytes = [0x02, 0x00, 0x03, 0x00, 0x01, 0x00, 0x02, 0x00, 0x03, 0x00];
+
+        let map = parse(&bytes, true).unwrap();
+
+        assert_eq!((map.width, map.height), (2, 2));
+        assert!(map.layers.iter().all(|layer| layer.cells.len() == 4));
+        assert_eq!(map.layers[0].cells[2].index, 3);
+        assert_eq!(map.anomalies.len(), 3);
+    }
+
+    #[test]
+    fn lenient_header_with_huge_dimensions() {
+        let map = parse(&[0xFF, 0xFF, 0xFF, 0xFF], true).unwrap();
+
+        assert_eq!((map.width, map.height), (0xFFFF, 0));
+        assert!(map.layers.iter().all(|layer| layer.cells.is_empty()));
+    }
+
+    #[test]
+    fn invalid_texture_kind() {
+        let bytes = [0x01, 0x00, 0x01, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00];
+
+        assert!(matches!(
+            parse(&bytes, false),
+            Err(Error::Invalid(Anomaly::InvalidTextureKind {
+                offset: 5,
+                ..
+            }))
+        ));
+        assert_eq!(parse(&bytes, true).unwrap().anomalies.len(), 1);
+    }
+}
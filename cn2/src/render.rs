@@ -0,0 +1,179 @@
+//! Composites maps into images on the CPU.
+
+use image::{imageops, Pixel};
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    error::{Error, Result},
+    map::{Cell, Map, TextureKind, ANM_FRAMES, SHEET_COLUMNS, TILE_SIZE},
+};
+
+mod font;
+mod overlay;
+
+pub use image::{Rgba, RgbaImage};
+pub use overlay::{draw_triggers, legend, trigger_color};
+
+/// The decoded `_base`, `_var` and `_anm` sprite sheets of a map.
+pub struct Sheets {
+    pub base: RgbaImage,
+    pub var: RgbaImage,
+    pub anm: RgbaImage,
+}
+
+impl Sheets {
+    /// Opens the textures of `map`. Relative texture paths are resolved
+    /// against `assets_dir`.
+    pub fn open(map: &Map, assets_dir: &Path) -> Result<Sheets> {
+        Ok(Sheets {
+            base: open_sheet(&resolve(assets_dir, &map.base))?,
+            var: open_sheet(&resolve(assets_dir, &map.var))?,
+            anm: open_sheet(&resolve(assets_dir, &map.anm))?,
+        })
+    }
+
+    pub fn get(&self, kind: TextureKind) -> &RgbaImage {
+        match kind {
+            TextureKind::Base => &self.base,
+            TextureKind::Var => &self.var,
+            TextureKind::Anm => &self.anm,
+        }
+    }
+}
+
+fn resolve(assets_dir: &Path, path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        assets_dir.join(path)
+    }
+}
+
+fn open_sheet(path: &Path) -> Result<RgbaImage> {
+    image::open(path)
+        .map(|image| image.to_rgba())
+        .map_err(|e| Error::Texture {
+            path: path.to_path_buf(),
+            reason: e.to_string(),
+        })
+}
+
+#[derive(Clone, Debug)]
+pub struct RenderOptions {
+    /// Only draw this layer, otherwise the upper layer is drawn over the lower.
+    pub layer: Option<usize>,
+    /// Frame of the `Anm` tile animation to draw, `0..ANM_FRAMES`.
+    pub frame: u8,
+    /// Integer factor the image is scaled up by.
+    pub scale: u32,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            layer: None,
+            frame: 0,
+            scale: 1,
+        }
+    }
+}
+
+/// Pixel position of the top left corner of cell `idx`.
+pub fn cell_origin(map: &Map, idx: usize) -> (u32, u32) {
+    (
+        (idx % map.width) as u32 * TILE_SIZE,
+        (idx / map.width) as u32 * TILE_SIZE,
+    )
+}
+
+pub fn render(map: &Map, sheets: &Sheets, options: &RenderOptions) -> RgbaImage {
+    let mut image = RgbaImage::new(map.width as u32 * TILE_SIZE, map.height as u32 * TILE_SIZE);
+
+    for (layer_idx, layer) in map.layers.iter().enumerate() {
+        match options.layer {
+            Some(only) if only != layer_idx => continue,
+            _ => {}
+        }
+
+        for (idx, cell) in layer.cells.iter().enumerate() {
+            let (x, y) = cell_origin(map, idx);
+            draw_tile(&mut image, sheets, cell, options.frame, x, y);
+        }
+    }
+
+    scale(&image, options.scale)
+}
+
+/// Blends the tile of `cell` onto `image` at `(x, y)`. Tiles outside of their
+/// sheet are skipped.
+fn draw_tile(image: &mut RgbaImage, sheets: &Sheets, cell: &Cell, frame: u8, x: u32, y: u32) {
+    let sheet = sheets.get(cell.kind);
+
+    let sprite = if cell.kind == TextureKind::Anm {
+        u32::from(cell.index) + u32::from(frame % ANM_FRAMES)
+    } else {
+        u32::from(cell.index)
+    };
+
+    let src_x = (sprite % SHEET_COLUMNS) * TILE_SIZE;
+    let src_y = (sprite / SHEET_COLUMNS) * TILE_SIZE;
+
+    if src_x + TILE_SIZE > sheet.width() || src_y + TILE_SIZE > sheet.height() {
+        return;
+    }
+
+    for dy in 0..TILE_SIZE {
+        for dx in 0..TILE_SIZE {
+            let src = sheet.get_pixel(src_x + dx, src_y + dy);
+            image.get_pixel_mut(x + dx, y + dy).blend(src);
+        }
+    }
+}
+
+/// Blends a translucent `color` over the tile whose top left corner is at
+/// `(x, y)`.
+pub fn tint_tile(image: &mut RgbaImage, x: u32, y: u32, color: Rgba<u8>) {
+    for py in y..(y + TILE_SIZE).min(image.height()) {
+        for px in x..(x + TILE_SIZE).min(image.width()) {
+            image.get_pixel_mut(px, py).blend(&color);
+        }
+    }
+}
+
+/// Scales `image` up by an integer `factor` without smoothing.
+pub fn scale(image: &RgbaImage, factor: u32) -> RgbaImage {
+    if factor <= 1 {
+        return image.clone();
+    }
+
+    RgbaImage::from_fn(image.width() * factor, image.height() * factor, |x, y| {
+        *image.get_pixel(x / factor, y / factor)
+    })
+}
+
+/// Places `right` next to `left`, aligned to the top.
+pub fn beside(left: &RgbaImage, right: &RgbaImage) -> RgbaImage {
+    let mut image = RgbaImage::new(
+        left.width() + right.width(),
+        left.height().max(right.height()),
+    );
+
+    imageops::replace(&mut image, left, 0, 0);
+    imageops::replace(&mut image, right, left.width(), 0);
+
+    image
+}
+
+/// Places `bottom` under `top`, aligned to the left.
+pub fn above(top: &RgbaImage, bottom: &RgbaImage) -> RgbaImage {
+    let mut image = RgbaImage::new(
+        top.width().max(bottom.width()),
+        top.height() + bottom.height(),
+    );
+
+    imageops::replace(&mut image, top, 0, 0);
+    imageops::replace(&mut image, bottom, 0, top.height());
+
+    image
+}
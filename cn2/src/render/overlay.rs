@@ -0,0 +1,182 @@
+//! Images of the trigger grid of a map layer.
+
+use image::{Rgba, RgbaImage};
+
+use super::{
+    cell_origin,
+    font::{draw_text, text_width, GLYPH_HEIGHT},
+    tint_tile,
+};
+use crate::{
+    map::{Cell, Map, TriggerKind, TILE_SIZE},
+    triggers::TriggerTable,
+};
+
+const TEXT_SCALE: u32 = 2;
+const SWATCH_SIZE: u32 = 16;
+const PADDING: u32 = 4;
+
+const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const BACKGROUND: Rgba<u8> = Rgba([32, 32, 32, 255]);
+
+/// The colour a trigger is drawn with. Treasures and exits share a colour
+/// per kind, unrecognised bytes each get their own.
+pub fn trigger_color(kind: TriggerKind) -> Rgba<u8> {
+    match kind {
+        TriggerKind::Uninitialized => Rgba([128, 128, 128, 255]),
+        TriggerKind::Passable => Rgba([0, 200, 0, 255]),
+        TriggerKind::Blocker => Rgba([220, 0, 0, 255]),
+        TriggerKind::UpperLowerDelta => Rgba([0, 160, 255, 255]),
+        TriggerKind::LowerUpperDelta => Rgba([0, 0, 200, 255]),
+        TriggerKind::Hidden => Rgba([128, 0, 128, 255]),
+        TriggerKind::Bridge => Rgba([160, 100, 40, 255]),
+        TriggerKind::Damage => Rgba([255, 120, 0, 255]),
+        TriggerKind::BottomTransparent => Rgba([0, 200, 200, 255]),
+        TriggerKind::BottomHidden => Rgba([255, 0, 255, 255]),
+        TriggerKind::Unknown7 => Rgba([255, 255, 140, 255]),
+        TriggerKind::Unknown12 => Rgba([180, 255, 100, 255]),
+        TriggerKind::Unknown13 => Rgba([255, 180, 180, 255]),
+        TriggerKind::Treasure(_) => Rgba([255, 200, 0, 255]),
+        TriggerKind::Exit(_) => WHITE,
+        TriggerKind::Unknown(n) => Rgba([n.wrapping_mul(97), 255 - n / 2, n.wrapping_mul(53), 255]),
+    }
+}
+
+/// Text drawn on a tile with this trigger, if any.
+fn tile_label(kind: TriggerKind) -> Option<String> {
+    match kind {
+        TriggerKind::Treasure(n) | TriggerKind::Exit(n) => Some(n.to_string()),
+        TriggerKind::Unknown(n) => Some(format!("{:02X}", n)),
+        _ => None,
+    }
+}
+
+/// The legend entry a trigger is listed under.
+fn legend_label(kind: TriggerKind) -> String {
+    match kind {
+        TriggerKind::Treasure(_) => "Treasure".to_string(),
+        TriggerKind::Exit(_) => "Exit".to_string(),
+        TriggerKind::Unknown(n) => format!("Unknown({:02X})", n),
+        kind => format!("{:?}", kind),
+    }
+}
+
+/// The trigger of `cell` as `triggers` decodes its raw byte, or the trigger it
+/// was decoded to if it kept none.
+fn decoded_trigger(cell: &Cell, triggers: &TriggerTable) -> TriggerKind {
+    cell.raw_trigger
+        .map_or(cell.trigger, |value| triggers.decode(value))
+}
+
+/// Draws the triggers of `layer` over `image`, a render of `map` at scale 1,
+/// decoded with `triggers`. `opacity` is applied to the trigger colours,
+/// labels are drawn opaque.
+pub fn draw_triggers(
+    image: &mut RgbaImage,
+    map: &Map,
+    layer: usize,
+    triggers: &TriggerTable,
+    opacity: u8,
+) {
+    let cells = match map.layers.get(layer) {
+        Some(layer) => &layer.cells,
+        None => return,
+    };
+
+    for (idx, cell) in cells.iter().enumerate() {
+        let (x, y) = cell_origin(map, idx);
+        let trigger = decoded_trigger(cell, triggers);
+
+        let mut color = trigger_color(trigger);
+        color.0[3] = opacity;
+
+        tint_tile(image, x, y, color);
+
+        if let Some(label) = tile_label(trigger) {
+            let label_x = x + TILE_SIZE.saturating_sub(text_width(&label, TEXT_SCALE)) / 2;
+            let label_y = y + (TILE_SIZE - GLYPH_HEIGHT * TEXT_SCALE) / 2;
+
+            draw_text(image, label_x, label_y, &label, TEXT_SCALE, BLACK);
+        }
+    }
+}
+
+/// A legend of the triggers found in `layer`, decoded and ordered by
+/// `triggers`, laid out in as many columns as fit in `width` pixels.
+pub fn legend(map: &Map, layer: usize, triggers: &TriggerTable, width: u32) -> RgbaImage {
+    let mut entries: Vec<TriggerKind> = vec![];
+
+    if let Some(layer) = map.layers.get(layer) {
+        for cell in layer.cells.iter() {
+            let kind = match decoded_trigger(cell, triggers) {
+                TriggerKind::Treasure(_) => TriggerKind::Treasure(0),
+                TriggerKind::Exit(_) => TriggerKind::Exit(0),
+                kind => kind,
+            };
+
+            if !entries.contains(&kind) {
+                entries.push(kind);
+            }
+        }
+    }
+
+    // Treasures and exits stand for any id, so they sort by their first byte
+    entries.sort_by_key(|kind| {
+        triggers.encode(*kind).or_else(|| {
+            triggers
+                .rules
+                .iter()
+                .find(|rule| rule.kind == kind.name())
+                .map(|rule| rule.first)
+        })
+    });
+
+    let labels = entries
+        .iter()
+        .map(|kind| legend_label(*kind))
+        .collect::<Vec<_>>();
+
+    let row_height = SWATCH_SIZE + PADDING;
+    let column_width = labels
+        .iter()
+        .map(|label| PADDING + SWATCH_SIZE + PADDING + text_width(label, TEXT_SCALE) + PADDING)
+        .max()
+        .unwrap_or(0);
+
+    let columns = (width / column_width.max(1)).max(1) as usize;
+    let rows = entries.chunks(columns).count();
+
+    let mut image = RgbaImage::from_pixel(
+        width.max(column_width),
+        rows as u32 * row_height + PADDING,
+        BACKGROUND,
+    );
+
+    for (n, (kind, label)) in entries.iter().zip(labels.iter()).enumerate() {
+        let x = (n % columns) as u32 * column_width + PADDING;
+        let y = (n / columns) as u32 * row_height + PADDING;
+
+        fill(&mut image, x, y, SWATCH_SIZE, trigger_color(*kind));
+
+        let text_y = y + (SWATCH_SIZE - GLYPH_HEIGHT * TEXT_SCALE) / 2;
+        draw_text(
+            &mut image,
+            x + SWATCH_SIZE + PADDING,
+            text_y,
+            label,
+            TEXT_SCALE,
+            WHITE,
+        );
+    }
+
+    image
+}
+
+fn fill(image: &mut RgbaImage, x: u32, y: u32, size: u32, color: Rgba<u8>) {
+    for py in y..(y + size).min(image.height()) {
+        for px in x..(x + size).min(image.width()) {
+            image.put_pixel(px, py, color);
+        }
+    }
+}
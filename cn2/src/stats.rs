@@ -0,0 +1,101 @@
+use serde::Serialize;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::map::{Map, TextureKind, TriggerKind};
+
+/// Counts of the triggers and tiles used by one or more maps.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Stats {
+    pub maps: usize,
+    pub cells: usize,
+    /// Cells per trigger kind, over both layers. Treasures and exits are
+    /// counted together regardless of their id.
+    pub triggers: BTreeMap<String, usize>,
+    /// Raw trigger bytes behind the kinds that aren't understood yet.
+    pub unknown_bytes: BTreeMap<String, BTreeMap<u8, usize>>,
+    pub tiles: BTreeMap<String, TileUsage>,
+    /// Cells per `Anm` tile index.
+    pub anm: BTreeMap<u8, usize>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TileUsage {
+    pub cells: usize,
+    /// Distinct tile indices used.
+    pub indices: BTreeSet<u8>,
+}
+
+impl Stats {
+    pub fn of(map: &Map) -> Stats {
+        let mut stats = Stats {
+            maps: 1,
+            ..Default::default()
+        };
+
+        for layer in map.layers.iter() {
+            for cell in layer.cells.iter() {
+                stats.cells += 1;
+
+                *stats.triggers.entry(cell.trigger.name()).or_default() += 1;
+
+                if let (true, Some(raw)) = (is_unknown(cell.trigger), cell.raw_trigger) {
+                    *stats
+                        .unknown_bytes
+                        .entry(cell.trigger.name())
+                        .or_default()
+                        .entry(raw)
+                        .or_default() += 1;
+                }
+
+                let tiles = stats.tiles.entry(format!("{:?}", cell.kind)).or_default();
+                tiles.cells += 1;
+                tiles.indices.insert(cell.index);
+
+                if cell.kind == TextureKind::Anm {
+                    *stats.anm.entry(cell.index).or_default() += 1;
+                }
+            }
+        }
+
+        stats
+    }
+
+    /// Adds the counts of `other` to these.
+    pub fn merge(&mut self, other: &Stats) {
+        self.maps += other.maps;
+        self.cells += other.cells;
+
+        for (name, count) in other.triggers.iter() {
+            *self.triggers.entry(name.clone()).or_default() += count;
+        }
+
+        for (name, bytes) in other.unknown_bytes.iter() {
+            let counts = self.unknown_bytes.entry(name.clone()).or_default();
+
+            for (byte, count) in bytes.iter() {
+                *counts.entry(*byte).or_default() += count;
+            }
+        }
+
+        for (kind, usage) in other.tiles.iter() {
+            let tiles = self.tiles.entry(kind.clone()).or_default();
+            tiles.cells += usage.cells;
+            tiles.indices.extend(usage.indices.iter());
+        }
+
+        for (index, count) in other.anm.iter() {
+            *self.anm.entry(*index).or_default() += count;
+        }
+    }
+}
+
+fn is_unknown(kind: TriggerKind) -> bool {
+    matches!(
+        kind,
+        TriggerKind::Unknown7
+            | TriggerKind::Unknown12
+            | TriggerKind::Unknown13
+            | TriggerKind::Unknown(_)
+    )
+}
@@ -0,0 +1,98 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::{Error, Result},
+    path::PathExt,
+};
+
+/// The three sprite sheets a map's cells index into.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Textures {
+    pub base: PathBuf,
+    pub var: PathBuf,
+    pub anm: PathBuf,
+}
+
+/// Decides which textures belong to a map, given the name of its `.cn2` file.
+pub trait TextureResolver {
+    fn resolve(&self, name: &Path) -> Result<Textures>;
+}
+
+/// Always resolves to the same textures, e.g. `Textures::default()` when a
+/// map's textures don't matter.
+impl TextureResolver for Textures {
+    fn resolve(&self, _name: &Path) -> Result<Textures> {
+        Ok(self.clone())
+    }
+}
+
+impl<F: Fn(&Path) -> Result<Textures>> TextureResolver for F {
+    fn resolve(&self, name: &Path) -> Result<Textures> {
+        self(name)
+    }
+}
+
+/// Finds textures next to `.cn2` files in a directory, see `find_textures`.
+#[derive(Clone, Debug, Default)]
+pub struct DirResolver {
+    /// Map names are resolved relative to this, the working directory if
+    /// empty.
+    pub dir: PathBuf,
+}
+
+impl DirResolver {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        DirResolver { dir: dir.into() }
+    }
+}
+
+impl TextureResolver for DirResolver {
+    fn resolve(&self, name: &Path) -> Result<Textures> {
+        find_textures(self.dir.join(name))
+    }
+}
+
+/// Finds the `<identifier>_*_base.png`, `_var.png` and `_anm.png` textures
+/// next to a `.cn2` file, where the identifier is the part of the `.cn2` file
+/// name before the first `_`.
+pub fn find_textures(path: impl AsRef<Path>) -> Result<Textures> {
+    let (mut base, mut var, mut anm) = (None, None, None);
+
+    let path = path.as_ref();
+    let meta_name = path.file_name_str();
+
+    let identifier = meta_name.split('_').next().unwrap_or_default();
+    let dir = match path.parent() {
+        Some(dir) if dir != Path::new("") => dir,
+        _ => Path::new("."),
+    };
+
+    if identifier.is_empty() {
+        return Err(Error::MissingTextures(path.to_path_buf()));
+    }
+
+    for entry in fs::read_dir(dir)?.flatten() {
+        let entry_path = entry.path();
+
+        let entry_ext = entry_path.ext_str().to_lowercase();
+        let entry_stem = entry_path.file_stem_str().to_lowercase();
+
+        if entry_stem.starts_with(&format!("{}_", identifier)) && entry_ext == "png" {
+            if entry_stem.ends_with("_base") {
+                base = Some(entry_path);
+            } else if entry_stem.ends_with("_var") {
+                var = Some(entry_path);
+            } else if entry_stem.ends_with("_anm") {
+                anm = Some(entry_path);
+            }
+        }
+    }
+
+    match (base, var, anm) {
+        (Some(base), Some(var), Some(anm)) => Ok(Textures { base, var, anm }),
+        _ => Err(Error::MissingTextures(path.to_path_buf())),
+    }
+}
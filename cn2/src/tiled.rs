@@ -0,0 +1,848 @@
+//! Conversion to and from the JSON map format of the Tiled editor.
+//!
+//! Both layers become tile layers over three embedded tilesets, one per
+//! `TextureKind`. The triggers of each layer become an object layer with one
+//! tile sized object per cell that isn't plainly passable. Kind bytes with
+//! bits the parser doesn't understand go in a hidden object layer per layer,
+//! one object per cell.
+//!
+//! Importing reads the first two tile layers as the lower and upper layer.
+//! Trigger objects may cover several cells, cells no object covers are
+//! passable. Raw kind objects only apply to the cell at their top left.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use std::{
+    path::{Path, PathBuf},
+    str,
+};
+
+use crate::{
+    error::{Error, Result},
+    map::{
+        Cell, Layer, Map, TextureKind, TriggerKind, ANM_FRAMES, ANM_FRAME_MS, SHEET_COLUMNS,
+        SHEET_TILES, TILE_SIZE,
+    },
+    textures::Textures,
+    triggers::TriggerTable,
+};
+
+/// Bits of a global tile id that flip or rotate the tile.
+const FLIP_FLAGS: u32 = 0xF000_0000;
+
+/// Names of the tile layers, lower layer first.
+pub const LAYER_NAMES: [&str; 2] = ["lower", "upper"];
+
+/// Names of the trigger object layers, lower layer first.
+pub const TRIGGER_LAYER_NAMES: [&str; 2] = ["lower triggers", "upper triggers"];
+
+/// Names of the hidden object layers holding raw kind bytes, lower layer
+/// first.
+pub const RAW_KIND_LAYER_NAMES: [&str; 2] = ["lower raw kinds", "upper raw kinds"];
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TiledMap {
+    #[serde(rename = "type", default)]
+    pub kind: String,
+    /// A number in files written before Tiled 1.6, a string after.
+    #[serde(default)]
+    pub version: Value,
+    #[serde(default)]
+    pub tiledversion: String,
+    #[serde(default)]
+    pub orientation: String,
+    #[serde(default)]
+    pub renderorder: String,
+    pub width: usize,
+    pub height: usize,
+    pub tilewidth: u32,
+    pub tileheight: u32,
+    #[serde(default)]
+    pub infinite: bool,
+    #[serde(default)]
+    pub nextlayerid: u32,
+    #[serde(default)]
+    pub nextobjectid: u32,
+    pub layers: Vec<TiledLayer>,
+    pub tilesets: Vec<Tileset>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub properties: Vec<Property>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum TiledLayer {
+    #[serde(rename = "tilelayer")]
+    Tiles(TileLayer),
+    #[serde(rename = "objectgroup")]
+    Objects(ObjectLayer),
+    /// Image and group layers, which are ignored.
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TileLayer {
+    #[serde(default)]
+    pub id: u32,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub x: i32,
+    #[serde(default)]
+    pub y: i32,
+    pub width: usize,
+    pub height: usize,
+    #[serde(default)]
+    pub opacity: f32,
+    #[serde(default)]
+    pub visible: bool,
+    pub data: TileData,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum TileData {
+    /// Global tile ids, row by row. `0` is an empty cell.
+    Gids(Vec<u32>),
+    /// Base64 data, which isn't supported.
+    Encoded(String),
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ObjectLayer {
+    #[serde(default)]
+    pub id: u32,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub x: i32,
+    #[serde(default)]
+    pub y: i32,
+    #[serde(default)]
+    pub opacity: f32,
+    #[serde(default)]
+    pub visible: bool,
+    #[serde(default)]
+    pub draworder: String,
+    #[serde(default)]
+    pub objects: Vec<Object>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Object {
+    #[serde(default)]
+    pub id: u32,
+    #[serde(default)]
+    pub name: String,
+    /// Renamed to `class` in Tiled 1.9.
+    #[serde(rename = "type", alias = "class", default)]
+    pub kind: String,
+    pub x: f64,
+    pub y: f64,
+    #[serde(default)]
+    pub width: f64,
+    #[serde(default)]
+    pub height: f64,
+    #[serde(default)]
+    pub rotation: f64,
+    #[serde(default)]
+    pub visible: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub properties: Vec<Property>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Tileset {
+    pub firstgid: u32,
+    /// Set instead of the other fields for tilesets in their own file, which
+    /// aren't supported.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub image: String,
+    #[serde(default)]
+    pub imagewidth: u32,
+    #[serde(default)]
+    pub imageheight: u32,
+    #[serde(default)]
+    pub tilewidth: u32,
+    #[serde(default)]
+    pub tileheight: u32,
+    #[serde(default)]
+    pub columns: u32,
+    #[serde(default)]
+    pub tilecount: u32,
+    #[serde(default)]
+    pub margin: u32,
+    #[serde(default)]
+    pub spacing: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tiles: Vec<TileInfo>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub properties: Vec<Property>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TileInfo {
+    pub id: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub animation: Vec<Frame>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Frame {
+    pub tileid: u32,
+    pub duration: u32,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Property {
+    pub name: String,
+    #[serde(rename = "type", default)]
+    pub kind: String,
+    pub value: Value,
+}
+
+impl Property {
+    pub fn string(name: &str, value: impl Into<String>) -> Property {
+        Property {
+            name: name.to_string(),
+            kind: "string".to_string(),
+            value: Value::String(value.into()),
+        }
+    }
+
+    pub fn int(name: &str, value: i64) -> Property {
+        Property {
+            name: name.to_string(),
+            kind: "int".to_string(),
+            value: Value::from(value),
+        }
+    }
+}
+
+/// Global id of the first tile of each tileset, indexed by `TextureKind`.
+pub fn first_gid(kind: TextureKind) -> u32 {
+    1 + u32::from(kind.to_byte()) * SHEET_TILES
+}
+
+/// The id of `Treasure`, `Exit` and `Unknown` triggers.
+pub fn trigger_id(kind: TriggerKind) -> Option<u8> {
+    match kind {
+        TriggerKind::Treasure(n) | TriggerKind::Exit(n) | TriggerKind::Unknown(n) => Some(n),
+        _ => None,
+    }
+}
+
+impl TiledMap {
+    /// Converts `map`, with `images` as the tileset image paths written to the
+    /// file. Tiled resolves them relative to the map file.
+    pub fn from_map(map: &Map, images: &Textures) -> TiledMap {
+        let mut layers = vec![];
+        let mut next_id = 1;
+        let mut next_object_id = 1;
+
+        for (layer, name) in map.layers.iter().zip(LAYER_NAMES.iter()) {
+            layers.push(TiledLayer::Tiles(TileLayer {
+                id: next_id,
+                name: name.to_string(),
+                x: 0,
+                y: 0,
+                width: map.width,
+                height: map.height,
+                opacity: 1.0,
+                visible: true,
+                data: TileData::Gids(
+                    layer
+                        .cells
+                        .iter()
+                        .map(|cell| first_gid(cell.kind) + u32::from(cell.index))
+                        .collect(),
+                ),
+            }));
+
+            next_id += 1;
+        }
+
+        for (layer, name) in map.layers.iter().zip(TRIGGER_LAYER_NAMES.iter()) {
+            let mut objects = vec![];
+
+            for (idx, cell) in layer.cells.iter().enumerate() {
+                // Passable cells are the default, leave them out unless they
+                // carry unknown bits
+                if cell.trigger == TriggerKind::Passable && cell.raw_trigger.unwrap_or(0) == 0 {
+                    continue;
+                }
+
+                let mut properties = vec![];
+
+                if let Some(raw) = cell.raw_trigger {
+                    properties.push(Property::int("raw", i64::from(raw)));
+                }
+
+                if let Some(id) = trigger_id(cell.trigger) {
+                    properties.push(Property::int("id", i64::from(id)));
+                }
+
+                objects.push(Object {
+                    id: next_object_id,
+                    name: format!("{:?}", cell.trigger),
+                    kind: cell.trigger.name(),
+                    x: f64::from((idx % map.width) as u32 * TILE_SIZE),
+                    y: f64::from((idx / map.width) as u32 * TILE_SIZE),
+                    width: f64::from(TILE_SIZE),
+                    height: f64::from(TILE_SIZE),
+                    rotation: 0.0,
+                    visible: true,
+                    properties,
+                });
+
+                next_object_id += 1;
+            }
+
+            layers.push(TiledLayer::Objects(ObjectLayer {
+                id: next_id,
+                name: name.to_string(),
+                x: 0,
+                y: 0,
+                opacity: 0.5,
+                visible: true,
+                draworder: "topdown".to_string(),
+                objects,
+            }));
+
+            next_id += 1;
+        }
+
+        for (layer, name) in map.layers.iter().zip(RAW_KIND_LAYER_NAMES.iter()) {
+            let mut objects = vec![];
+
+            for (idx, cell) in layer.cells.iter().enumerate() {
+                let raw = match cell.raw_kind {
+                    Some(raw) => raw,
+                    None => continue,
+                };
+
+                objects.push(Object {
+                    id: next_object_id,
+                    name: format!("{:#04x}", raw),
+                    kind: "RawKind".to_string(),
+                    x: f64::from((idx % map.width) as u32 * TILE_SIZE),
+                    y: f64::from((idx / map.width) as u32 * TILE_SIZE),
+                    width: f64::from(TILE_SIZE),
+                    height: f64::from(TILE_SIZE),
+                    rotation: 0.0,
+                    visible: true,
+                    properties: vec![Property::int("raw_kind", i64::from(raw))],
+                });
+
+                next_object_id += 1;
+            }
+
+            if objects.is_empty() {
+                continue;
+            }
+
+            layers.push(TiledLayer::Objects(ObjectLayer {
+                id: next_id,
+                name: name.to_string(),
+                x: 0,
+                y: 0,
+                opacity: 1.0,
+                visible: false,
+                draworder: "topdown".to_string(),
+                objects,
+            }));
+
+            next_id += 1;
+        }
+
+        let tilesets = vec![
+            tileset(TextureKind::Base, "base", &images.base, &map.base),
+            tileset(TextureKind::Var, "var", &images.var, &map.var),
+            tileset(TextureKind::Anm, "anm", &images.anm, &map.anm),
+        ];
+
+        let mut properties = vec![];
+
+        if !map.trailing.is_empty() {
+            let hex = map
+                .trailing
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>();
+
+            properties.push(Property::string("trailing", hex));
+        }
+
+        TiledMap {
+            kind: "map".to_string(),
+            version: Value::from("1.2"),
+            tiledversion: "1.3.0".to_string(),
+            orientation: "orthogonal".to_string(),
+            renderorder: "right-down".to_string(),
+            width: map.width,
+            height: map.height,
+            tilewidth: TILE_SIZE,
+            tileheight: TILE_SIZE,
+            infinite: false,
+            nextlayerid: next_id,
+            nextobjectid: next_object_id,
+            layers,
+            tilesets,
+            properties,
+        }
+    }
+}
+
+impl TiledMap {
+    /// Converts the map back, checking it still fits a `.cn2` file. Texture
+    /// paths come from the `path` property of each tileset, or its image. Raw
+    /// trigger bytes are kept while they decode to their object's trigger with
+    /// `triggers`.
+    pub fn to_map(&self, triggers: &TriggerTable) -> Result<Map> {
+        if self.infinite {
+            return Err(Error::Tiled("infinite maps aren't supported".into()));
+        }
+
+        if self.width > u16::MAX as usize || self.height > u16::MAX as usize {
+            return Err(Error::Tiled(format!(
+                "{}x{} doesn't fit a `.cn2` header",
+                self.width, self.height
+            )));
+        }
+
+        let tilesets = self
+            .tilesets
+            .iter()
+            .map(|tileset| Ok((tileset_kind(tileset)?, tileset)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let texture = |kind: TextureKind| {
+            tilesets
+                .iter()
+                .find(|(k, _)| *k == kind)
+                .map(|(_, tileset)| {
+                    match property(&tileset.properties, "path").and_then(Value::as_str) {
+                        Some(path) => PathBuf::from(path),
+                        None => PathBuf::from(&tileset.image),
+                    }
+                })
+                .ok_or_else(|| Error::Tiled(format!("no tileset for {:?} tiles", kind)))
+        };
+
+        let tile_layers = self
+            .layers
+            .iter()
+            .filter_map(|layer| match layer {
+                TiledLayer::Tiles(layer) => Some(layer),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        if tile_layers.len() != 2 {
+            return Err(Error::Tiled(format!(
+                "expected 2 tile layers, found {}",
+                tile_layers.len()
+            )));
+        }
+
+        let mut layers = tile_layers
+            .iter()
+            .enumerate()
+            .map(|(idx, layer)| self.read_tiles(idx, layer, &tilesets))
+            .collect::<Result<Vec<_>>>()?;
+
+        for (idx, name) in TRIGGER_LAYER_NAMES.iter().enumerate() {
+            if let Some(objects) = self.object_layer(name) {
+                self.read_triggers(objects, &mut layers[idx], triggers)?;
+            }
+        }
+
+        for (idx, name) in RAW_KIND_LAYER_NAMES.iter().enumerate() {
+            if let Some(objects) = self.object_layer(name) {
+                self.read_raw_kinds(objects, &mut layers[idx])?;
+            }
+        }
+
+        let trailing = match property(&self.properties, "trailing").and_then(Value::as_str) {
+            Some(hex) => decode_hex(hex)?,
+            None => vec![],
+        };
+
+        Ok(Map {
+            width: self.width,
+            height: self.height,
+            base: texture(TextureKind::Base)?,
+            var: texture(TextureKind::Var)?,
+            anm: texture(TextureKind::Anm)?,
+            layers,
+            trailing,
+            anomalies: vec![],
+        })
+    }
+
+    fn read_tiles(
+        &self,
+        idx: usize,
+        layer: &TileLayer,
+        tilesets: &[(TextureKind, &Tileset)],
+    ) -> Result<Layer> {
+        let gids = match &layer.data {
+            TileData::Gids(gids) => gids,
+            TileData::Encoded(_) => {
+                return Err(Error::Tiled(format!(
+                    "layer `{}` is base64 encoded, save it with the CSV layer format",
+                    layer.name
+                )))
+            }
+        };
+
+        if gids.len() != self.width * self.height {
+            return Err(Error::Tiled(format!(
+                "layer `{}` has {} tiles, expected {}",
+                layer.name,
+                gids.len(),
+                self.width * self.height
+            )));
+        }
+
+        let cells = gids
+            .iter()
+            .enumerate()
+            .map(|(cell, &gid)| {
+                let at = || {
+                    format!(
+                        "layer {} ({}, {})",
+                        idx,
+                        cell % self.width,
+                        cell / self.width
+                    )
+                };
+
+                if gid & FLIP_FLAGS != 0 {
+                    return Err(Error::Tiled(format!(
+                        "tile at {} is flipped or rotated",
+                        at()
+                    )));
+                }
+
+                // Empty cells become the first base tile, which is what the
+                // game leaves unused upper layer cells as
+                if gid == 0 {
+                    return Ok(Cell {
+                        trigger: TriggerKind::Passable,
+                        ..Default::default()
+                    });
+                }
+
+                let (kind, tileset) = tilesets
+                    .iter()
+                    .filter(|(_, tileset)| tileset.firstgid <= gid)
+                    .max_by_key(|(_, tileset)| tileset.firstgid)
+                    .ok_or_else(|| {
+                        Error::Tiled(format!("tile {} at {} has no tileset", gid, at()))
+                    })?;
+
+                let index = gid - tileset.firstgid;
+
+                if index > u32::from(u8::MAX) {
+                    return Err(Error::Tiled(format!(
+                        "tile {} of `{}` at {} doesn't fit a u8 index",
+                        index,
+                        tileset.name,
+                        at()
+                    )));
+                }
+
+                Ok(Cell {
+                    index: index as u8,
+                    kind: *kind,
+                    trigger: TriggerKind::Passable,
+                    ..Default::default()
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Layer { cells })
+    }
+
+    fn object_layer(&self, name: &str) -> Option<&ObjectLayer> {
+        self.layers.iter().find_map(|layer| match layer {
+            TiledLayer::Objects(layer) if layer.name == name => Some(layer),
+            _ => None,
+        })
+    }
+
+    /// Sets the trigger of every cell an object of `objects` covers, in order.
+    fn read_triggers(
+        &self,
+        objects: &ObjectLayer,
+        layer: &mut Layer,
+        triggers: &TriggerTable,
+    ) -> Result<()> {
+        for object in objects.objects.iter() {
+            let byte = |name: &str| byte_property(objects, object, name);
+
+            let trigger = TriggerKind::from_name(&object.kind, byte("id")?.unwrap_or(0))
+                .ok_or_else(|| {
+                    Error::Tiled(format!(
+                        "object {} in `{}` has unknown trigger type `{}`",
+                        object.id, objects.name, object.kind
+                    ))
+                })?;
+
+            // Keep the raw byte only while it still decodes to the same kind
+            let raw_trigger = byte("raw")?.filter(|&raw| triggers.decode(raw) == trigger);
+
+            let size = f64::from(TILE_SIZE);
+
+            let x0 = (object.x / size).floor();
+            let y0 = (object.y / size).floor();
+            let x1 = ((object.x + object.width) / size).ceil().max(x0 + 1.0);
+            let y1 = ((object.y + object.height) / size).ceil().max(y0 + 1.0);
+
+            if x0 < 0.0 || y0 < 0.0 || x1 > self.width as f64 || y1 > self.height as f64 {
+                return Err(Error::Tiled(format!(
+                    "object {} in `{}` lies outside the map",
+                    object.id, objects.name
+                )));
+            }
+
+            for y in y0 as usize..y1 as usize {
+                for x in x0 as usize..x1 as usize {
+                    let cell = &mut layer.cells[y * self.width + x];
+
+                    cell.trigger = trigger;
+                    cell.raw_trigger = raw_trigger;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sets the raw kind byte of the cell at the top left of each object of
+    /// `objects`. Objects only ever describe one cell, so resizing them in
+    /// Tiled doesn't spread the byte to neighbouring tiles.
+    fn read_raw_kinds(&self, objects: &ObjectLayer, layer: &mut Layer) -> Result<()> {
+        for object in objects.objects.iter() {
+            let raw_kind = byte_property(objects, object, "raw_kind")?.ok_or_else(|| {
+                Error::Tiled(format!(
+                    "object {} in `{}` has no `raw_kind`",
+                    object.id, objects.name
+                ))
+            })?;
+
+            let size = f64::from(TILE_SIZE);
+
+            let x = (object.x / size).floor();
+            let y = (object.y / size).floor();
+
+            if x < 0.0 || y < 0.0 || x >= self.width as f64 || y >= self.height as f64 {
+                return Err(Error::Tiled(format!(
+                    "object {} in `{}` lies outside the map",
+                    object.id, objects.name
+                )));
+            }
+
+            layer.cells[y as usize * self.width + x as usize].raw_kind = Some(raw_kind);
+        }
+
+        Ok(())
+    }
+}
+
+/// A byte property of `object`, failing if it doesn't fit one.
+fn byte_property(objects: &ObjectLayer, object: &Object, name: &str) -> Result<Option<u8>> {
+    match property(&object.properties, name) {
+        None => Ok(None),
+        Some(value) => value
+            .as_u64()
+            .filter(|&value| value <= u64::from(u8::MAX))
+            .map(|value| Some(value as u8))
+            .ok_or_else(|| {
+                Error::Tiled(format!(
+                    "`{}` of object {} in `{}` should be a byte, found {}",
+                    name, object.id, objects.name, value
+                ))
+            }),
+    }
+}
+
+fn tileset_kind(tileset: &Tileset) -> Result<TextureKind> {
+    if let Some(source) = &tileset.source {
+        return Err(Error::Tiled(format!(
+            "tileset {} is external, embed it in the map",
+            source
+        )));
+    }
+
+    match tileset.name.as_str() {
+        "base" => Ok(TextureKind::Base),
+        "var" => Ok(TextureKind::Var),
+        "anm" => Ok(TextureKind::Anm),
+        name => Err(Error::Tiled(format!(
+            "tileset `{}` should be named base, var or anm",
+            name
+        ))),
+    }
+}
+
+fn property<'a>(properties: &'a [Property], name: &str) -> Option<&'a Value> {
+    properties
+        .iter()
+        .find(|property| property.name == name)
+        .map(|property| &property.value)
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>> {
+    let invalid = || Error::Tiled(format!("`{}` isn't valid hex", hex));
+
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            str::from_utf8(pair)
+                .ok()
+                .filter(|pair| pair.len() == 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(invalid)
+        })
+        .collect()
+}
+
+/// An embedded tileset for the sheet of `kind`. The map's own texture path is
+/// kept as the `path` property, so importing restores it.
+fn tileset(kind: TextureKind, name: &str, image: &Path, path: &Path) -> Tileset {
+    let size = SHEET_COLUMNS * TILE_SIZE;
+
+    // Anm tiles show their index and the following tiles in turn
+    let tiles = if kind == TextureKind::Anm {
+        (0..=SHEET_TILES - u32::from(ANM_FRAMES))
+            .map(|id| TileInfo {
+                id,
+                animation: (0..u32::from(ANM_FRAMES))
+                    .map(|frame| Frame {
+                        tileid: id + frame,
+                        duration: ANM_FRAME_MS,
+                    })
+                    .collect(),
+            })
+            .collect()
+    } else {
+        vec![]
+    };
+
+    Tileset {
+        firstgid: first_gid(kind),
+        source: None,
+        name: name.to_string(),
+        image: image.to_string_lossy().replace('\\', "/"),
+        imagewidth: size,
+        imageheight: size,
+        tilewidth: TILE_SIZE,
+        tileheight: TILE_SIZE,
+        columns: SHEET_COLUMNS,
+        tilecount: SHEET_TILES,
+        margin: 0,
+        spacing: 0,
+        tiles,
+        properties: vec![Property::string("path", path.to_string_lossy())],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Property, TiledLayer, TiledMap, TRIGGER_LAYER_NAMES};
+    use crate::{Error, Map, ParseOptions, Textures, TriggerRule, TriggerTable};
+
+    const MAP: &[u8] = &[
+        0x02, 0x00, 0x01, 0x00, // 2x1
+        0x01, 0x00, 0x02, 0x41, // lower layer
+        0x03, 0x01, 0x04, 0x02, // upper layer
+        0x01, 0x43, // lower triggers
+        0x60, 0x99, // upper triggers
+    ];
+
+    /// The default table, with exits moved to 0x60.
+    fn triggers() -> TriggerTable {
+        let mut table = TriggerTable::default();
+        table.rules.insert(
+            0,
+            TriggerRule {
+                first: 0x60,
+                last: 0x7F,
+                kind: "Exit".to_string(),
+                mask: 0x1F,
+            },
+        );
+
+        table
+    }
+
+    fn tiled(triggers: &TriggerTable) -> TiledMap {
+        let options = ParseOptions {
+            triggers: triggers.clone(),
+            ..ParseOptions::default()
+        };
+        let map = Map::parse(MAP, "", &Textures::default(), &options).unwrap();
+
+        TiledMap::from_map(&map, &Textures::default())
+    }
+
+    #[test]
+    fn round_trip() {
+        let triggers = triggers();
+        let map = tiled(&triggers).to_map(&triggers).unwrap();
+
+        assert_eq!(map.to_bytes(&triggers).unwrap(), MAP);
+    }
+
+    #[test]
+    fn rejects_ids_beyond_a_byte() {
+        let triggers = TriggerTable::default();
+        let mut tiled = tiled(&triggers);
+
+        for layer in tiled.layers.iter_mut() {
+            if let TiledLayer::Objects(layer) = layer {
+                for object in layer.objects.iter_mut() {
+                    object.properties.push(Property::int("id", 300));
+                }
+            }
+        }
+
+        match tiled.to_map(&triggers) {
+            Err(Error::Tiled(reason)) => assert!(reason.contains("object 1")),
+            other => panic!("expected a Tiled error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn keeps_raw_kinds_per_tile_under_merged_triggers() {
+        let triggers = TriggerTable::default();
+        let mut tiled = tiled(&triggers);
+
+        // Merge the lower triggers into one rectangle over both cells, of
+        // which only the second has a raw kind byte
+        for layer in tiled.layers.iter_mut() {
+            if let TiledLayer::Objects(layer) = layer {
+                if layer.name == TRIGGER_LAYER_NAMES[0] {
+                    layer.objects.truncate(1);
+                    layer.objects[0].width = 64.0;
+                }
+            }
+        }
+
+        let map = tiled.to_map(&triggers).unwrap();
+        let lower = &map.layers[0].cells;
+
+        assert_eq!(lower[0].trigger, lower[1].trigger);
+        assert_eq!(lower[0].raw_kind, None);
+        assert_eq!(lower[1].raw_kind, Some(0x41));
+        assert_eq!(&map.to_bytes(&triggers).unwrap()[4..8], &MAP[4..8]);
+    }
+}
@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+
+use std::{fs, path::Path, result};
+
+use crate::{
+    error::{Error, Result},
+    map::{Map, TriggerKind},
+};
+
+/// Maps trigger bytes to `TriggerKind`s, so hypotheses about unknown triggers
+/// can be tried by editing a data file.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TriggerTable {
+    /// Checked in order, the first rule containing a byte decodes it. Bytes
+    /// no rule contains decode to `Unknown(byte)`.
+    pub rules: Vec<TriggerRule>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TriggerRule {
+    /// First byte of the range, inclusive.
+    pub first: u8,
+    /// Last byte of the range, inclusive.
+    pub last: u8,
+    /// Name of a `TriggerKind` variant, e.g. `Blocker` or `Exit`.
+    pub kind: String,
+    /// Bits of the byte kept as the id of `Treasure`, `Exit` and `Unknown`.
+    #[serde(default = "full_mask")]
+    pub mask: u8,
+}
+
+fn full_mask() -> u8 {
+    0xFF
+}
+
+/// The table `cn2/triggers.ron` describes, which is the default.
+const DEFAULT_TABLE: &str = include_str!("../triggers.ron");
+
+impl TriggerTable {
+    /// Reads a table from a RON file, checking every rule names a known kind.
+    pub fn load(path: impl AsRef<Path>) -> Result<TriggerTable> {
+        let path = path.as_ref();
+
+        TriggerTable::from_ron(&fs::read(path)?)
+            .map_err(|e| Error::Serde(format!("{}: {}", path.display(), e)))
+    }
+
+    fn from_ron(bytes: &[u8]) -> result::Result<TriggerTable, String> {
+        let table: TriggerTable = ron::de::from_bytes(bytes).map_err(|e| e.to_string())?;
+
+        for rule in table.rules.iter() {
+            if rule.first > rule.last {
+                return Err(format!(
+                    "range {:#04X}..={:#04X} of `{}` is empty",
+                    rule.first, rule.last, rule.kind
+                ));
+            }
+
+            if TriggerKind::from_name(&rule.kind, 0).is_none() {
+                return Err(format!("unknown trigger kind `{}`", rule.kind));
+            }
+        }
+
+        Ok(table)
+    }
+
+    pub fn decode(&self, value: u8) -> TriggerKind {
+        self.rules
+            .iter()
+            .find(|rule| (rule.first..=rule.last).contains(&value))
+            .and_then(|rule| TriggerKind::from_name(&rule.kind, value & rule.mask))
+            .unwrap_or(TriggerKind::Unknown(value))
+    }
+
+    /// The first byte that decodes to `kind`, `None` if none does.
+    /// `Uninitialized` is encoded as `Passable`.
+    pub fn encode(&self, kind: TriggerKind) -> Option<u8> {
+        let kind = match kind {
+            TriggerKind::Uninitialized => TriggerKind::Passable,
+            kind => kind,
+        };
+        let name = kind.name();
+
+        self.rules
+            .iter()
+            .filter(|rule| rule.kind == name)
+            .flat_map(|rule| rule.first..=rule.last)
+            .chain(match kind {
+                TriggerKind::Unknown(value) => Some(value),
+                _ => None,
+            })
+            .find(|&value| self.decode(value) == kind)
+    }
+}
+
+impl Default for TriggerTable {
+    fn default() -> Self {
+        TriggerTable::from_ron(DEFAULT_TABLE.as_bytes())
+            .expect("cn2/triggers.ron is a valid trigger table")
+    }
+}
+
+impl Map {
+    /// Decodes the trigger of every cell again from its raw byte, e.g. after
+    /// opening an exported map. Cells without one are encoded with the
+    /// default table first, those it can't encode are left as they are.
+    pub fn decode_triggers(&mut self, table: &TriggerTable) {
+        let default = TriggerTable::default();
+
+        for layer in self.layers.iter_mut() {
+            for cell in layer.cells.iter_mut() {
+                if let Some(value) = cell.raw_trigger.or_else(|| default.encode(cell.trigger)) {
+                    cell.trigger = table.decode(value);
+                    cell.raw_trigger = Some(value);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TriggerTable;
+    use crate::map::TriggerKind;
+
+    #[test]
+    fn default_table() {
+        let table = TriggerTable::default();
+
+        assert_eq!(table.decode(0x01), TriggerKind::Blocker);
+        assert_eq!(table.decode(0x11), TriggerKind::BottomHidden);
+        assert_eq!(table.decode(0x43), TriggerKind::Exit(3));
+        assert_eq!(table.decode(0x99), TriggerKind::Unknown(0x99));
+    }
+
+    #[test]
+    fn encode_inverts_decode() {
+        let table = TriggerTable::default();
+
+        for value in 0..=u8::MAX {
+            assert_eq!(table.encode(table.decode(value)), Some(value));
+        }
+
+        assert_eq!(table.encode(TriggerKind::Uninitialized), Some(0x00));
+        assert_eq!(table.encode(TriggerKind::Exit(0x20)), None);
+    }
+}
@@ -0,0 +1,258 @@
+use serde::Serialize;
+
+use std::{fmt, path::PathBuf};
+
+use crate::map::{Map, TextureKind, ANM_FRAMES, SHEET_COLUMNS, TILE_SIZE};
+
+/// Something about a map or its textures that would make the game draw the
+/// wrong tiles or panic.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub enum Problem {
+    /// A texture couldn't be opened.
+    Texture {
+        kind: TextureKind,
+        path: PathBuf,
+        reason: String,
+    },
+    /// A texture isn't the grid of `SHEET_COLUMNS` by `SHEET_COLUMNS` tiles of
+    /// `TILE_SIZE` pixels the game slices sprite sheets into.
+    SheetSize {
+        kind: TextureKind,
+        path: PathBuf,
+        width: u32,
+        height: u32,
+    },
+    /// A cell's tile, or a frame of an `Anm` cell, lies outside its sheet. The
+    /// game finds tile `n` at column `n % SHEET_COLUMNS`, row
+    /// `n / SHEET_COLUMNS`.
+    IndexOutOfRange {
+        layer: usize,
+        x: usize,
+        y: usize,
+        kind: TextureKind,
+        index: u8,
+        /// Tiles across the sheet.
+        columns: u32,
+        /// Tiles down the sheet.
+        rows: u32,
+    },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::Texture { kind, path, reason } => write!(
+                f,
+                "{:?} texture {} can't be opened: {}",
+                kind,
+                path.display(),
+                reason
+            ),
+            Problem::SheetSize {
+                kind,
+                path,
+                width,
+                height,
+            } => write!(
+                f,
+                "{:?} texture {} is {}x{}, expected {}x{}",
+                kind,
+                path.display(),
+                width,
+                height,
+                SHEET_COLUMNS * TILE_SIZE,
+                SHEET_COLUMNS * TILE_SIZE
+            ),
+            Problem::IndexOutOfRange {
+                layer,
+                x,
+                y,
+                kind,
+                index,
+                columns,
+                rows,
+            } if *kind == TextureKind::Anm => write!(
+                f,
+                "layer {} ({}, {}): Anm frames {}..={} don't fit a sheet of {}x{} tiles",
+                layer,
+                x,
+                y,
+                index,
+                u32::from(*index) + u32::from(ANM_FRAMES) - 1,
+                columns,
+                rows
+            ),
+            Problem::IndexOutOfRange {
+                layer,
+                x,
+                y,
+                kind,
+                index,
+                columns,
+                rows,
+            } => write!(
+                f,
+                "layer {} ({}, {}): {:?} tile {} doesn't fit a sheet of {}x{} tiles",
+                layer, x, y, kind, index, columns, rows
+            ),
+        }
+    }
+}
+
+impl Map {
+    /// Checks every cell's tile fits a full sheet of `SHEET_COLUMNS` by
+    /// `SHEET_COLUMNS` tiles, including all `ANM_FRAMES` frames of `Anm` cells.
+    pub fn check_indices(&self) -> Vec<Problem> {
+        self.check_indices_with(|_| (SHEET_COLUMNS, SHEET_COLUMNS))
+    }
+
+    /// Checks every cell's tile fits its sheet, `sheet` giving the columns and
+    /// rows of tiles in the sheet of a kind.
+    fn check_indices_with(&self, sheet: impl Fn(TextureKind) -> (u32, u32)) -> Vec<Problem> {
+        let mut problems = vec![];
+
+        for (layer, cells) in self.layers.iter().enumerate() {
+            for (idx, cell) in cells.cells.iter().enumerate() {
+                let frames = if cell.kind == TextureKind::Anm {
+                    ANM_FRAMES
+                } else {
+                    1
+                };
+
+                let (columns, rows) = sheet(cell.kind);
+
+                let fits = (0..u32::from(frames))
+                    .map(|frame| u32::from(cell.index) + frame)
+                    .all(|tile| tile % SHEET_COLUMNS < columns && tile / SHEET_COLUMNS < rows);
+
+                if !fits {
+                    let (x, y) = self.position(idx);
+
+                    problems.push(Problem::IndexOutOfRange {
+                        layer,
+                        x,
+                        y,
+                        kind: cell.kind,
+                        index: cell.index,
+                        columns,
+                        rows,
+                    });
+                }
+            }
+        }
+
+        problems
+    }
+
+    /// Checks the textures of this map are full sprite sheets and every
+    /// cell's tile fits the sheet it indexes into. Relative texture paths are
+    /// resolved against `assets_dir`.
+    #[cfg(feature = "render")]
+    pub fn validate(&self, assets_dir: &std::path::Path) -> Vec<Problem> {
+        let mut problems = vec![];
+        let mut sheets = [(SHEET_COLUMNS, SHEET_COLUMNS); 3];
+
+        for (kind, path) in [
+            (TextureKind::Base, &self.base),
+            (TextureKind::Var, &self.var),
+            (TextureKind::Anm, &self.anm),
+        ]
+        .iter()
+        {
+            let path = if path.is_absolute() {
+                path.to_path_buf()
+            } else {
+                assets_dir.join(path)
+            };
+
+            let (width, height) = match image::image_dimensions(&path) {
+                Ok(dimensions) => dimensions,
+                Err(e) => {
+                    problems.push(Problem::Texture {
+                        kind: *kind,
+                        path,
+                        reason: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+
+            let expected = SHEET_COLUMNS * TILE_SIZE;
+
+            if width != expected || height != expected {
+                sheets[kind.to_byte() as usize] = (
+                    (width / TILE_SIZE).min(SHEET_COLUMNS),
+                    (height / TILE_SIZE).min(SHEET_COLUMNS),
+                );
+
+                problems.push(Problem::SheetSize {
+                    kind: *kind,
+                    path,
+                    width,
+                    height,
+                });
+            }
+        }
+
+        problems.extend(self.check_indices_with(|kind| sheets[kind.to_byte() as usize]));
+
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Problem;
+    use crate::{Cell, Layer, Map, TextureKind};
+
+    fn map(cells: &[(TextureKind, u8)]) -> Map {
+        Map {
+            width: cells.len(),
+            height: 1,
+            layers: vec![Layer {
+                cells: cells
+                    .iter()
+                    .map(|&(kind, index)| Cell {
+                        index,
+                        kind,
+                        ..Cell::default()
+                    })
+                    .collect(),
+            }],
+            ..Map::default()
+        }
+    }
+
+    fn out_of_range(problems: &[Problem]) -> Vec<(usize, u8)> {
+        problems
+            .iter()
+            .filter_map(|problem| match problem {
+                Problem::IndexOutOfRange { x, index, .. } => Some((*x, *index)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn narrow_sheet() {
+        let map = map(&[
+            (TextureKind::Base, 3),
+            (TextureKind::Base, 5),
+            (TextureKind::Base, 0x43),
+            (TextureKind::Anm, 2),
+            (TextureKind::Anm, 0x20),
+        ]);
+
+        // 4 tiles across and 16 down
+        let problems = map.check_indices_with(|_| (4, 16));
+
+        assert_eq!(out_of_range(&problems), vec![(1, 5), (3, 2)]);
+    }
+
+    #[test]
+    fn last_anm_frames() {
+        let map = map(&[(TextureKind::Anm, 252), (TextureKind::Anm, 253)]);
+
+        assert_eq!(out_of_range(&map.check_indices()), vec![(1, 253)]);
+    }
+}
@@ -0,0 +1,198 @@
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use std::io;
+
+use crate::{
+    error::{Error, Result},
+    map::Map,
+    triggers::TriggerTable,
+};
+
+impl Map {
+    /// Encodes the map in the `.cn2` layout: the u16 width and height, both
+    /// layers of index / kind pairs, the trigger table of each layer, then any
+    /// trailing bytes. Triggers are encoded with `triggers`.
+    pub fn write<W: io::Write>(&self, w: &mut W, triggers: &TriggerTable) -> Result<()> {
+        let cell_count = self.width * self.height;
+
+        if self.width > u16::MAX as usize || self.height > u16::MAX as usize {
+            return Err(Error::Encode(format!(
+                "{}x{} doesn't fit a `.cn2` header",
+                self.width, self.height
+            )));
+        }
+
+        if self.layers.len() != 2 {
+            return Err(Error::Encode(format!(
+                "expected 2 layers, found {}",
+                self.layers.len()
+            )));
+        }
+
+        for (idx, layer) in self.layers.iter().enumerate() {
+            if layer.cells.len() != cell_count {
+                return Err(Error::Encode(format!(
+                    "layer {} has {} cells, expected {}",
+                    idx,
+                    layer.cells.len(),
+                    cell_count
+                )));
+            }
+        }
+
+        let trigger_bytes = self.trigger_bytes(triggers)?;
+
+        w.write_u16::<LittleEndian>(self.width as u16)?;
+        w.write_u16::<LittleEndian>(self.height as u16)?;
+
+        for layer in self.layers.iter() {
+            for cell in layer.cells.iter() {
+                w.write_u8(cell.index)?;
+                w.write_u8(cell.kind_byte())?;
+            }
+        }
+
+        for bytes in trigger_bytes.iter() {
+            w.write_all(bytes)?;
+        }
+
+        w.write_all(&self.trailing)?;
+
+        Ok(())
+    }
+
+    /// Encodes the map into a new `.cn2` buffer.
+    pub fn to_bytes(&self, triggers: &TriggerTable) -> Result<Vec<u8>> {
+        let mut buf = vec![];
+        self.write(&mut buf, triggers)?;
+
+        Ok(buf)
+    }
+
+    /// The trigger byte of every cell of each layer, failing on the first
+    /// trigger `triggers` has no byte for.
+    pub(crate) fn trigger_bytes(&self, triggers: &TriggerTable) -> Result<Vec<Vec<u8>>> {
+        self.layers
+            .iter()
+            .enumerate()
+            .map(|(layer, cells)| {
+                cells
+                    .cells
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, cell)| {
+                        cell.trigger_byte(triggers).ok_or_else(|| {
+                            Error::Encode(format!(
+                                "{:?} at layer {} ({}, {}) has no byte in the trigger table",
+                                cell.trigger,
+                                layer,
+                                idx % self.width,
+                                idx / self.width
+                            ))
+                        })
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Map, ParseOptions, Textures, TriggerKind, TriggerTable};
+
+    /// A 2x2 map with unknown kind bits, a non-zero upper trigger table and
+    /// trailing bytes.
+    const MAP: &[u8] = &[
+        0x02, 0x00, 0x02, 0x00, // 2x2
+        0x01, 0x00, 0x02, 0x01, 0x03, 0x42, 0x04, 0x80, // lower layer
+        0x05, 0x00, 0x06, 0x01, 0x07, 0x02, 0x08, 0x21, // upper layer
+        0x00, 0x01, 0x25, 0x43, // lower triggers
+        0x10, 0x11, 0x07, 0x99, // upper triggers
+        0xDE, 0xAD, 0xBE, // trailing
+    ];
+
+    fn parse(bytes: &[u8]) -> Map {
+        Map::parse(bytes, "", &Textures::default(), &ParseOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn cn2_round_trip() {
+        assert_eq!(parse(MAP).to_bytes(&TriggerTable::default()).unwrap(), MAP);
+    }
+
+    #[test]
+    fn edited_trigger_replaces_raw_byte() {
+        let mut map = parse(MAP);
+        map.layers[0].cells[0].trigger = TriggerKind::Exit(3);
+
+        let bytes = map.to_bytes(&TriggerTable::default()).unwrap();
+
+        assert_eq!(bytes[20], 0x43);
+        assert_eq!(&bytes[21..], &MAP[21..]);
+    }
+
+    #[test]
+    fn unencodable_trigger() {
+        let mut map = parse(MAP);
+        map.layers[1].cells[3].trigger = TriggerKind::Exit(0x30);
+
+        assert!(map.to_bytes(&TriggerTable::default()).is_err());
+    }
+
+    #[test]
+    fn cn2_round_trip_without_trailing_bytes() {
+        let bytes = &MAP[..MAP.len() - 3];
+
+        assert_eq!(
+            parse(bytes).to_bytes(&TriggerTable::default()).unwrap(),
+            bytes
+        );
+    }
+
+    #[test]
+    fn compact_round_trip() {
+        let map = parse(MAP);
+        let triggers = TriggerTable::default();
+
+        let mut compact = vec![];
+        map.write_compact(&mut compact, &triggers).unwrap();
+
+        let read = Map::read_compact(&compact, &ParseOptions::default()).unwrap();
+
+        assert_eq!(read, map);
+        assert_eq!(read.to_bytes(&triggers).unwrap(), MAP);
+    }
+
+    #[test]
+    fn rejects_mismatched_layers() {
+        let mut map = parse(MAP);
+        map.layers[1].cells.pop();
+
+        assert!(map.to_bytes(&TriggerTable::default()).is_err());
+    }
+
+    #[test]
+    fn lenient_round_trip_keeps_invalid_kinds() {
+        let mut bytes = MAP.to_vec();
+        bytes[7] = 0x83;
+
+        let options = ParseOptions {
+            lenient: true,
+            ..ParseOptions::default()
+        };
+        let map = Map::parse(&bytes, "", &Textures::default(), &options).unwrap();
+        let triggers = &options.triggers;
+
+        assert_eq!(map.to_bytes(triggers).unwrap(), bytes);
+
+        let mut compact = vec![];
+        map.write_compact(&mut compact, triggers).unwrap();
+
+        assert!(Map::read_compact(&compact, &ParseOptions::default()).is_err());
+
+        let read = Map::read_compact(&compact, &options).unwrap();
+
+        assert_eq!(read.to_bytes(triggers).unwrap(), bytes);
+    }
+}
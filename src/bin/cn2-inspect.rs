@@ -0,0 +1,222 @@
+use anyhow::{bail, Context, Error};
+use cn2::{Layout, Region, TextureKind, TriggerTable};
+use structopt::StructOpt;
+
+use std::fs;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::result;
+
+const LAYER_NAMES: [&str; 2] = ["lower", "upper"];
+
+fn main() -> Result<()> {
+    let opts = Opts::from_args();
+
+    let bytes =
+        fs::read(&opts.input).with_context(|| format!("Couldn't read {}", opts.input.display()))?;
+
+    let triggers = match &opts.triggers {
+        Some(path) => TriggerTable::load(path)?,
+        None => TriggerTable::default(),
+    };
+
+    println!("{}: {} bytes", opts.input.display(), bytes.len());
+    println!();
+
+    let layout = match Layout::from_header(&bytes) {
+        Some(layout) => layout,
+        None => {
+            print_section(&bytes, "Header", 0..4, "truncated");
+            hexdump(&bytes, 0..bytes.len());
+            return Ok(());
+        }
+    };
+
+    print_sections(&bytes, &layout, opts.hexdump);
+
+    if let Some(region) = opts.cell.or(opts.range) {
+        println!();
+        print_cells(&bytes, &layout, region, &triggers)?;
+    }
+
+    Ok(())
+}
+
+type Result<T> = result::Result<T, Error>;
+
+fn print_sections(bytes: &[u8], layout: &Layout, dump: bool) {
+    print_section(
+        bytes,
+        "Header",
+        layout.header(),
+        &format!("width {}, height {}", layout.width, layout.height),
+    );
+    if dump {
+        hexdump(bytes, layout.header());
+    }
+
+    for (layer, name) in LAYER_NAMES.iter().enumerate() {
+        print_section(
+            bytes,
+            &format!("Layer {} ({})", layer, name),
+            layout.layer(layer),
+            &format!("{} index / kind pairs", layout.cell_count()),
+        );
+        if dump {
+            hexdump(bytes, layout.layer(layer));
+        }
+    }
+
+    for (layer, name) in LAYER_NAMES.iter().enumerate() {
+        print_section(
+            bytes,
+            &format!("Triggers {} ({})", layer, name),
+            layout.triggers(layer),
+            &format!("{} trigger bytes", layout.cell_count()),
+        );
+        if dump {
+            hexdump(bytes, layout.triggers(layer));
+        }
+    }
+
+    if bytes.len() > layout.file_len() {
+        let trailing = layout.file_len()..bytes.len();
+
+        print_section(
+            bytes,
+            "Trailing",
+            trailing.clone(),
+            &format!("{} bytes after the trigger block", trailing.len()),
+        );
+        hexdump(bytes, trailing);
+    } else {
+        println!("No trailing bytes");
+    }
+}
+
+/// Prints a line describing the byte range of a section, noting whether the
+/// file ends before it does.
+fn print_section(bytes: &[u8], name: &str, range: Range<usize>, description: &str) {
+    let missing = if range.end > bytes.len() {
+        format!(
+            ", {} byte(s) missing",
+            range.end - range.start.max(bytes.len())
+        )
+    } else {
+        String::new()
+    };
+
+    println!(
+        "{:<20}{:#010x}..{:#010x}  {}{}",
+        name, range.start, range.end, description, missing
+    );
+}
+
+/// Prints the bytes of `range` that are present in the file, 16 per line.
+fn hexdump(bytes: &[u8], range: Range<usize>) {
+    let start = range.start.min(bytes.len());
+    let end = range.end.min(bytes.len());
+
+    for (line, chunk) in bytes[start..end].chunks(16).enumerate() {
+        let hex = chunk
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let ascii = chunk
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect::<String>();
+
+        println!("  {:08x}  {:<47}  |{}|", start + line * 16, hex, ascii);
+    }
+}
+
+fn print_cells(
+    bytes: &[u8],
+    layout: &Layout,
+    region: Region,
+    triggers: &TriggerTable,
+) -> Result<()> {
+    for y in region.y0..=region.y1 {
+        for x in region.x0..=region.x1 {
+            let idx = match layout.cell_at(x, y) {
+                Some(idx) => idx,
+                None => bail!(
+                    "({}, {}) is outside the {}x{} map",
+                    x,
+                    y,
+                    layout.width,
+                    layout.height
+                ),
+            };
+
+            println!("({}, {}) cell {}", x, y, idx);
+
+            for (layer, name) in LAYER_NAMES.iter().enumerate() {
+                let (index, kind, trigger) = layout.cell(layer, idx);
+
+                let kind_name = |value: u8| {
+                    let (kind, bits) = TextureKind::split(value);
+
+                    let name = match kind {
+                        Some(kind) => format!("{:?}", kind),
+                        None => "invalid".to_string(),
+                    };
+
+                    if bits != 0 {
+                        format!("{} + unknown bits {:#04x}", name, bits)
+                    } else {
+                        name
+                    }
+                };
+                let trigger_name = |value: u8| format!("{:?}", triggers.decode(value));
+
+                println!(
+                    "  {:<6} index {}  kind {}  trigger {}",
+                    name,
+                    describe(bytes, index, |value| value.to_string()),
+                    describe(bytes, kind, kind_name),
+                    describe(bytes, trigger, trigger_name),
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The byte at `offset` with its offset and meaning, or that it's missing.
+fn describe(bytes: &[u8], offset: usize, decode: impl Fn(u8) -> String) -> String {
+    match bytes.get(offset) {
+        Some(&value) => format!("{:#04x} {} @{:#x}", value, decode(value), offset),
+        None => format!("missing @{:#x}", offset),
+    }
+}
+
+#[derive(StructOpt)]
+#[structopt(name = "cn2-inspect", about = "Show the byte layout of a `.cn2` file", version = env!("CARGO_PKG_VERSION"))]
+pub struct Opts {
+    #[structopt(parse(from_os_str))]
+    pub input: PathBuf,
+    /// Decode the cell at `x,y`
+    #[structopt(short, long)]
+    pub cell: Option<Region>,
+    /// Decode the cells from `x0,y0` to `x1,y1`, inclusive
+    #[structopt(short, long, conflicts_with = "cell")]
+    pub range: Option<Region>,
+    /// Dump every section, not just trailing bytes
+    #[structopt(short = "x", long)]
+    pub hexdump: bool,
+    /// A RON trigger table to decode trigger bytes with, see
+    /// `cn2/triggers.ron`
+    #[structopt(short, long, parse(from_os_str))]
+    pub triggers: Option<PathBuf>,
+}
@@ -0,0 +1,203 @@
+use anyhow::{Context, Error};
+use cn2::{
+    find_textures,
+    render::{render, scale, tint_tile, RenderOptions, Rgba, Sheets},
+    Cell, CellChange, Map, MapDiff, MapFormat, ParseOptions, TriggerTable, TILE_SIZE,
+};
+use structopt::StructOpt;
+
+use std::path::{Path, PathBuf};
+use std::result;
+
+const LAYER_NAMES: [&str; 2] = ["lower", "upper"];
+
+/// Highlights tiles whose index or kind changed.
+const TILE_CHANGED: Rgba<u8> = Rgba([255, 0, 0, 140]);
+/// Highlights tiles where only the trigger changed.
+const TRIGGER_CHANGED: Rgba<u8> = Rgba([255, 255, 0, 140]);
+
+fn main() -> Result<()> {
+    let opts = Opts::from_args();
+
+    let mut before = open(&opts.before)?;
+    let mut after = open(&opts.after)?;
+
+    if let Some(path) = &opts.triggers {
+        let triggers = TriggerTable::load(path)?;
+
+        before.decode_triggers(&triggers);
+        after.decode_triggers(&triggers);
+    }
+
+    let diff = before.diff(&after);
+
+    if opts.json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+    } else {
+        print_diff(&diff);
+    }
+
+    if let Some(png) = &opts.png {
+        write_png(&after, &diff, png, &opts)?;
+    }
+
+    Ok(())
+}
+
+type Result<T> = result::Result<T, Error>;
+
+/// Opens a map without looking for its textures, which only the PNG needs.
+fn open(path: &Path) -> Result<Map> {
+    Map::open_without_textures(path, &ParseOptions::default())
+        .with_context(|| format!("Couldn't open {}", path.display()))
+}
+
+fn print_diff(diff: &MapDiff) {
+    if diff.is_empty() {
+        println!("Maps are identical");
+        return;
+    }
+
+    if let Some([(w0, h0), (w1, h1)]) = diff.dimensions {
+        println!("Dimensions: {}x{} -> {}x{}", w0, h0, w1, h1);
+    }
+
+    for change in diff.textures.iter() {
+        println!(
+            "{:?} texture: {} -> {}",
+            change.kind,
+            change.before.display(),
+            change.after.display()
+        );
+    }
+
+    if let Some([l0, l1]) = diff.layers {
+        println!("Layers: {} -> {}", l0, l1);
+    }
+
+    for change in diff.cells.iter() {
+        print_cell_change(change);
+    }
+
+    if let Some([t0, t1]) = diff.trailing {
+        println!("Trailing bytes: {} -> {}", t0, t1);
+    }
+
+    if !diff.cells.is_empty() {
+        println!("{} cell(s) changed", diff.cells.len());
+    }
+}
+
+/// The trigger of `cell`, along with its raw byte if it kept one.
+fn trigger_label(cell: &Cell) -> String {
+    match cell.raw_trigger {
+        Some(raw) => format!("{:?} ({:#04x})", cell.trigger, raw),
+        None => format!("{:?}", cell.trigger),
+    }
+}
+
+fn print_cell_change(change: &CellChange) {
+    let mut parts = vec![];
+    let (before, after) = (&change.before, &change.after);
+
+    if change.tile_changed() {
+        parts.push(format!(
+            "tile {:?} {} -> {:?} {}",
+            before.kind, before.index, after.kind, after.index
+        ));
+
+        if before.raw_kind.is_some() || after.raw_kind.is_some() {
+            parts.push(format!(
+                "kind byte {:#04x} -> {:#04x}",
+                before.kind_byte(),
+                after.kind_byte()
+            ));
+        }
+    }
+
+    if change.trigger_changed() {
+        parts.push(format!(
+            "trigger {} -> {}",
+            trigger_label(before),
+            trigger_label(after)
+        ));
+    }
+
+    println!(
+        "layer {} ({}) ({}, {}): {}",
+        change.layer,
+        LAYER_NAMES.get(change.layer).unwrap_or(&"extra"),
+        change.x,
+        change.y,
+        parts.join(", ")
+    );
+}
+
+/// Renders the later map with changed tiles highlighted.
+fn write_png(after: &Map, diff: &MapDiff, output: &Path, opts: &Opts) -> Result<()> {
+    // Textures of a `.cn2` map are found next to it rather than in the assets
+    let (map, assets) = if MapFormat::from_path(&opts.after) == Some(MapFormat::Cn2) {
+        let textures = find_textures(&opts.after)?;
+
+        let mut map = after.clone();
+        map.base = textures.base;
+        map.var = textures.var;
+        map.anm = textures.anm;
+
+        (map, PathBuf::new())
+    } else {
+        (after.clone(), opts.assets.clone())
+    };
+
+    let sheets = Sheets::open(&map, &assets)?;
+    let mut image = render(&map, &sheets, &RenderOptions::default());
+
+    for change in diff.cells.iter() {
+        let color = if change.tile_changed() {
+            TILE_CHANGED
+        } else {
+            TRIGGER_CHANGED
+        };
+
+        tint_tile(
+            &mut image,
+            change.x as u32 * TILE_SIZE,
+            change.y as u32 * TILE_SIZE,
+            color,
+        );
+    }
+
+    scale(&image, opts.scale)
+        .save(output)
+        .with_context(|| format!("Couldn't write {}", output.display()))?;
+
+    Ok(())
+}
+
+#[derive(StructOpt)]
+#[structopt(name = "map-diff", about = "Compare two maps", version = env!("CARGO_PKG_VERSION"))]
+pub struct Opts {
+    /// The earlier map, in any supported format
+    #[structopt(parse(from_os_str))]
+    pub before: PathBuf,
+    /// The later map, in any supported format
+    #[structopt(parse(from_os_str))]
+    pub after: PathBuf,
+    /// Print JSON instead of text
+    #[structopt(short, long)]
+    pub json: bool,
+    /// Write a render of the later map with changed tiles highlighted, red for
+    /// tile changes and yellow for trigger changes
+    #[structopt(short, long, parse(from_os_str))]
+    pub png: Option<PathBuf>,
+    /// Directory relative texture paths of exported maps are resolved against
+    #[structopt(short, long, parse(from_os_str), default_value = "assets")]
+    pub assets: PathBuf,
+    /// Scale the image up by this factor
+    #[structopt(short, long, default_value = "1")]
+    pub scale: u32,
+    /// A RON trigger table to decode trigger bytes with, see
+    /// `cn2/triggers.ron`
+    #[structopt(short, long, parse(from_os_str))]
+    pub triggers: Option<PathBuf>,
+}
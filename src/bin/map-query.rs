@@ -0,0 +1,94 @@
+use anyhow::{bail, Context, Error};
+use cn2::{Located, Map, ParseOptions, Region, Tile, TriggerKind, TriggerTable};
+use structopt::StructOpt;
+
+use std::path::PathBuf;
+use std::result;
+
+fn main() -> Result<()> {
+    let opts = Opts::from_args();
+
+    let mut map = Map::open_without_textures(&opts.map, &ParseOptions::default())
+        .with_context(|| format!("Couldn't open {}", opts.map.display()))?;
+
+    if let Some(path) = &opts.triggers {
+        map.decode_triggers(&TriggerTable::load(path)?);
+    }
+
+    let found = match opts.at {
+        Some(region) => cells_in(&map, region)?,
+        None => find(&map, &opts)?,
+    };
+
+    println!("{}", serde_json::to_string_pretty(&found)?);
+
+    Ok(())
+}
+
+type Result<T> = result::Result<T, Error>;
+
+/// Every layer's cell in `region`, which must be inside the map.
+fn cells_in(map: &Map, region: Region) -> Result<Vec<Located>> {
+    if map.cell_index(region.x1, region.y1).is_none() {
+        bail!(
+            "({}, {}) is outside the {}x{} map",
+            region.x1,
+            region.y1,
+            map.width,
+            map.height
+        );
+    }
+
+    let mut found = vec![];
+
+    for y in region.y0..=region.y1 {
+        for x in region.x0..=region.x1 {
+            found.extend(map.cells_at(x, y));
+        }
+    }
+
+    Ok(found)
+}
+
+/// Cells matching all of the given filters.
+fn find(map: &Map, opts: &Opts) -> Result<Vec<Located>> {
+    if opts.find_trigger.is_none() && opts.find_tile.is_none() && opts.region.is_none() {
+        bail!("Nothing to query, pass --at, --find-trigger, --find-tile or --region");
+    }
+
+    Ok(map.find(|cell, x, y| {
+        opts.find_trigger
+            .iter()
+            .all(|&trigger| cell.trigger == trigger)
+            && opts.find_tile.iter().all(|tile| tile.matches(cell))
+            && opts.region.iter().all(|region| region.contains(x, y))
+    }))
+}
+
+#[derive(StructOpt)]
+#[structopt(
+    name = "map-query",
+    about = "Look up cells of a map, printing them with their coordinates as JSON",
+    version = env!("CARGO_PKG_VERSION")
+)]
+pub struct Opts {
+    /// Map to query, in any supported format
+    #[structopt(parse(from_os_str))]
+    pub map: PathBuf,
+    /// Print the cell of both layers at `x,y`
+    #[structopt(long, conflicts_with_all = &["find-trigger", "find-tile", "region"])]
+    pub at: Option<Region>,
+    /// Only list cells with this trigger, e.g. `Blocker` or `Exit(3)`
+    #[structopt(long)]
+    pub find_trigger: Option<TriggerKind>,
+    /// Only list cells showing this tile, e.g. `var:65`
+    #[structopt(long)]
+    pub find_tile: Option<Tile>,
+    /// Only list cells from `x0,y0` to `x1,y1`, inclusive
+    #[structopt(long)]
+    pub region: Option<Region>,
+    /// A RON trigger table to decode trigger bytes with, see
+    /// `cn2/triggers.ron`
+    #[structopt(short, long, parse(from_os_str))]
+    pub triggers: Option<PathBuf>,
+}
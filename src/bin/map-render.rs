@@ -0,0 +1,142 @@
+use anyhow::{bail, Context, Error};
+use cn2::{
+    render::{above, beside, draw_triggers, legend, render, scale, RenderOptions, Sheets},
+    Map, MapFormat, ParseOptions, PathExt, TriggerTable, ANM_FRAMES,
+};
+use structopt::StructOpt;
+
+use std::path::PathBuf;
+use std::result;
+
+fn main() -> Result<()> {
+    let opts = Opts::from_args();
+
+    if opts.frame >= ANM_FRAMES {
+        bail!("--frame must be below {}", ANM_FRAMES);
+    }
+
+    if opts.scale == 0 {
+        bail!("--scale must be at least 1");
+    }
+
+    let triggers = match &opts.triggers {
+        Some(path) => TriggerTable::load(path)?,
+        None => TriggerTable::default(),
+    };
+
+    let options = ParseOptions {
+        triggers: triggers.clone(),
+        ..ParseOptions::default()
+    };
+    let map = Map::open(&opts.map, &options)
+        .with_context(|| format!("Couldn't open {}", opts.map.display()))?;
+
+    // Textures of a `.cn2` map are found next to it rather than in the assets
+    let assets_dir = if MapFormat::from_path(&opts.map) == Some(MapFormat::Cn2) {
+        PathBuf::new()
+    } else {
+        opts.assets.clone()
+    };
+    let sheets = Sheets::open(&map, &assets_dir)?;
+
+    let options = RenderOptions {
+        layer: opts.layer.map(|layer| layer.index()),
+        frame: opts.frame,
+        scale: 1,
+    };
+    let mut image = render(&map, &sheets, &options);
+
+    if let Some(layer) = opts.overlay {
+        if opts.beside {
+            let mut overlay = image.clone();
+            draw_triggers(&mut overlay, &map, layer.index(), &triggers, 255);
+
+            image = beside(&image, &overlay);
+        } else {
+            draw_triggers(&mut image, &map, layer.index(), &triggers, opts.opacity);
+        }
+
+        let legend = legend(&map, layer.index(), &triggers, image.width());
+        image = above(&image, &legend);
+    }
+
+    let image = scale(&image, opts.scale);
+
+    let output = opts
+        .output
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("{}.png", opts.map.file_stem_str())));
+
+    image
+        .save(&output)
+        .with_context(|| format!("Couldn't write {}", output.display()))?;
+
+    println!("{} -> {}", opts.map.display(), output.display());
+
+    Ok(())
+}
+
+type Result<T> = result::Result<T, Error>;
+
+#[derive(Copy, Clone)]
+pub enum LayerArg {
+    Lower,
+    Upper,
+}
+
+impl LayerArg {
+    fn index(self) -> usize {
+        match self {
+            LayerArg::Lower => 0,
+            LayerArg::Upper => 1,
+        }
+    }
+}
+
+impl std::str::FromStr for LayerArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> result::Result<Self, Self::Err> {
+        match s {
+            "lower" => Ok(LayerArg::Lower),
+            "upper" => Ok(LayerArg::Upper),
+            _ => Err(format!("Unknown layer `{}`", s)),
+        }
+    }
+}
+
+#[derive(StructOpt)]
+#[structopt(name = "map-render", about = "Render a map to a `.png` image", version = env!("CARGO_PKG_VERSION"))]
+pub struct Opts {
+    /// A `.cn2` file or an exported `.ron`, `.json` or `.bin` map
+    #[structopt(parse(from_os_str))]
+    pub map: PathBuf,
+    /// Output image, defaults to `<map>.png` in the current directory
+    #[structopt(short, long, parse(from_os_str))]
+    pub output: Option<PathBuf>,
+    /// Directory relative texture paths of exported maps are resolved against
+    #[structopt(short, long, parse(from_os_str), default_value = "assets")]
+    pub assets: PathBuf,
+    /// Only render one layer
+    #[structopt(short, long, possible_values = &["lower", "upper"])]
+    pub layer: Option<LayerArg>,
+    /// Draw the triggers of this layer, along with a legend
+    #[structopt(long, possible_values = &["lower", "upper"])]
+    pub overlay: Option<LayerArg>,
+    /// Draw the triggers next to the tiles instead of over them
+    #[structopt(short, long, requires = "overlay")]
+    pub beside: bool,
+    /// A RON trigger table to decode trigger bytes with, see
+    /// `cn2/triggers.ron`
+    #[structopt(short, long, parse(from_os_str))]
+    pub triggers: Option<PathBuf>,
+    /// Opacity of triggers drawn over the tiles, 0 to 255
+    #[structopt(long, default_value = "160")]
+    pub opacity: u8,
+    /// Scale the image up by this factor
+    #[structopt(short, long, default_value = "1")]
+    pub scale: u32,
+    /// Frame of the animated tiles to render, 0 to 3
+    #[structopt(short, long, default_value = "0")]
+    pub frame: u8,
+}
@@ -0,0 +1,139 @@
+use anyhow::{bail, Error};
+use cn2::{inputs, Map, ParseOptions, Stats, TriggerTable};
+use rayon::prelude::*;
+use serde::Serialize;
+use structopt::StructOpt;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::result;
+
+fn main() -> Result<()> {
+    let opts = Opts::from_args();
+
+    let inputs = inputs(&opts.input, opts.recursive)?;
+
+    if inputs.is_empty() {
+        bail!("No maps found in {}", opts.input.display());
+    }
+
+    let triggers = match &opts.triggers {
+        Some(path) => Some(TriggerTable::load(path)?),
+        None => None,
+    };
+
+    let results = inputs
+        .par_iter()
+        .map(|input| (input, map_stats(input, triggers.as_ref())))
+        .collect::<Vec<_>>();
+
+    let mut report = Report::default();
+    let mut failed = 0;
+
+    for (input, result) in results {
+        match result {
+            Ok(stats) => {
+                report.total.merge(&stats);
+                report.maps.insert(input.display().to_string(), stats);
+            }
+            Err(e) => {
+                eprintln!("Failed {}: {:#}", input.display(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    if opts.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        if !opts.total_only {
+            for (name, stats) in report.maps.iter() {
+                print_table(name, stats);
+            }
+        }
+
+        print_table("Total", &report.total);
+    }
+
+    if failed > 0 {
+        bail!("{} file(s) couldn't be read", failed);
+    }
+
+    Ok(())
+}
+
+type Result<T> = result::Result<T, Error>;
+
+#[derive(Default, Serialize)]
+struct Report {
+    maps: BTreeMap<String, Stats>,
+    total: Stats,
+}
+
+fn map_stats(input: &Path, triggers: Option<&TriggerTable>) -> Result<Stats> {
+    let mut map = Map::open_without_textures(input, &ParseOptions::default())?;
+
+    if let Some(triggers) = triggers {
+        map.decode_triggers(triggers);
+    }
+
+    Ok(Stats::of(&map))
+}
+
+fn print_table(name: &str, stats: &Stats) {
+    println!("{} ({} map(s), {} cells)", name, stats.maps, stats.cells);
+
+    println!("  Triggers");
+    for (kind, count) in stats.triggers.iter() {
+        println!("    {:<20}{:>8}", kind, count);
+    }
+
+    if !stats.unknown_bytes.is_empty() {
+        println!("  Unknown trigger bytes");
+        for (kind, bytes) in stats.unknown_bytes.iter() {
+            for (byte, count) in bytes.iter() {
+                println!("    {:<14}0x{:02X}{:>8}", kind, byte, count);
+            }
+        }
+    }
+
+    println!("  Tiles                  cells distinct");
+    for (kind, usage) in stats.tiles.iter() {
+        println!(
+            "    {:<16}{:>10}{:>9}",
+            kind,
+            usage.cells,
+            usage.indices.len()
+        );
+    }
+
+    if !stats.anm.is_empty() {
+        println!("  Anm tiles");
+        for (index, count) in stats.anm.iter() {
+            println!("    {:<20}{:>8}", index, count);
+        }
+    }
+
+    println!();
+}
+
+#[derive(StructOpt)]
+#[structopt(name = "map-stats", about = "Count triggers and tiles across maps", version = env!("CARGO_PKG_VERSION"))]
+pub struct Opts {
+    /// A map or a directory of `.cn2`, `.ron`, `.json` or `.bin` maps
+    #[structopt(parse(from_os_str))]
+    pub input: PathBuf,
+    /// Search the input directory recursively
+    #[structopt(short, long)]
+    pub recursive: bool,
+    /// Decode triggers again with this RON trigger table, see
+    /// `cn2/triggers.ron`
+    #[structopt(long, parse(from_os_str))]
+    pub triggers: Option<PathBuf>,
+    /// Print JSON instead of a table
+    #[structopt(short, long)]
+    pub json: bool,
+    /// Only print the totals table
+    #[structopt(short, long, conflicts_with = "json")]
+    pub total_only: bool,
+}
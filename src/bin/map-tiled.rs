@@ -0,0 +1,157 @@
+use anyhow::{Context, Error};
+use cn2::{
+    relative_path, tiled::TiledMap, Map, MapFormat, ParseOptions, PathExt, Textures, TriggerTable,
+};
+use structopt::StructOpt;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::result;
+
+fn main() -> Result<()> {
+    match Opts::from_args() {
+        Opts::Export {
+            map,
+            output,
+            assets,
+            triggers,
+        } => {
+            let output =
+                output.unwrap_or_else(|| PathBuf::from(format!("{}.tmj", map.file_stem_str())));
+
+            export(&map, &output, &assets, &trigger_table(triggers.as_deref())?)?;
+
+            println!("{} -> {}", map.display(), output.display());
+        }
+        Opts::Import {
+            tiled,
+            output,
+            triggers,
+        } => {
+            let output =
+                output.unwrap_or_else(|| PathBuf::from(format!("{}.ron", tiled.file_stem_str())));
+
+            import(&tiled, &output, &trigger_table(triggers.as_deref())?)?;
+
+            println!("{} -> {}", tiled.display(), output.display());
+        }
+    }
+
+    Ok(())
+}
+
+type Result<T> = result::Result<T, Error>;
+
+fn trigger_table(path: Option<&Path>) -> Result<TriggerTable> {
+    Ok(match path {
+        Some(path) => TriggerTable::load(path)?,
+        None => TriggerTable::default(),
+    })
+}
+
+fn export(input: &Path, output: &Path, assets: &Path, triggers: &TriggerTable) -> Result<()> {
+    let options = ParseOptions {
+        triggers: triggers.clone(),
+        ..ParseOptions::default()
+    };
+
+    let map =
+        Map::open(input, &options).with_context(|| format!("Couldn't open {}", input.display()))?;
+
+    let output_dir = match output.parent() {
+        Some(dir) if dir != Path::new("") => dir,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(output_dir)
+        .with_context(|| format!("Couldn't create {}", output_dir.display()))?;
+
+    // Textures of a `.cn2` map are found next to it rather than in the assets
+    let assets = if MapFormat::from_path(input) == Some(MapFormat::Cn2) {
+        Path::new("")
+    } else {
+        assets
+    };
+
+    let images = Textures {
+        base: image_path(&map.base, assets, output_dir)?,
+        var: image_path(&map.var, assets, output_dir)?,
+        anm: image_path(&map.anm, assets, output_dir)?,
+    };
+
+    let tiled = TiledMap::from_map(&map, &images);
+
+    fs::write(output, serde_json::to_vec_pretty(&tiled)?)
+        .with_context(|| format!("Couldn't write {}", output.display()))?;
+
+    Ok(())
+}
+
+fn import(input: &Path, output: &Path, triggers: &TriggerTable) -> Result<()> {
+    let bytes = fs::read(input).with_context(|| format!("Couldn't read {}", input.display()))?;
+
+    let tiled: TiledMap = serde_json::from_slice(&bytes)
+        .with_context(|| format!("{} isn't a Tiled JSON map", input.display()))?;
+
+    let map = tiled.to_map(triggers)?;
+
+    if let Some(dir) = output.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Couldn't create {}", dir.display()))?;
+    }
+
+    map.save(output, triggers)
+        .with_context(|| format!("Couldn't write {}", output.display()))?;
+
+    Ok(())
+}
+
+/// The path of `texture` relative to the Tiled map, which is how Tiled finds
+/// tileset images.
+fn image_path(texture: &Path, assets: &Path, output_dir: &Path) -> Result<PathBuf> {
+    let texture = assets.join(texture);
+
+    let absolute = fs::canonicalize(&texture)
+        .with_context(|| format!("Couldn't find texture {}", texture.display()))?;
+    let output_dir = fs::canonicalize(output_dir)?;
+
+    relative_path(&absolute, &output_dir).with_context(|| {
+        format!(
+            "{} can't be made relative to {}",
+            absolute.display(),
+            output_dir.display()
+        )
+    })
+}
+
+#[derive(StructOpt)]
+#[structopt(name = "map-tiled", about = "Convert maps to and from Tiled JSON maps", version = env!("CARGO_PKG_VERSION"))]
+pub enum Opts {
+    /// Write a map as a Tiled JSON map
+    Export {
+        /// A `.cn2` file or an exported `.ron`, `.json` or `.bin` map
+        #[structopt(parse(from_os_str))]
+        map: PathBuf,
+        /// Output file, defaults to `<map>.tmj` in the current directory
+        #[structopt(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+        /// Directory relative texture paths of exported maps are resolved against
+        #[structopt(short, long, parse(from_os_str), default_value = "assets")]
+        assets: PathBuf,
+        /// A RON trigger table to decode the trigger bytes of `.cn2` and `.bin`
+        /// maps with, see `cn2/triggers.ron`
+        #[structopt(short, long, parse(from_os_str))]
+        triggers: Option<PathBuf>,
+    },
+    /// Read a Tiled JSON map back into a map
+    Import {
+        /// A Tiled map saved as JSON, with embedded tilesets
+        #[structopt(parse(from_os_str))]
+        tiled: PathBuf,
+        /// Output map, its extension picks the format. Defaults to `<tiled>.ron`
+        #[structopt(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+        /// The RON trigger table the map was exported with, see
+        /// `cn2/triggers.ron`
+        #[structopt(short, long, parse(from_os_str))]
+        triggers: Option<PathBuf>,
+    },
+}
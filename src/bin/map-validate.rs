@@ -0,0 +1,86 @@
+use anyhow::{bail, Context, Error};
+use cn2::{inputs, Map, MapFormat, ParseOptions, Problem};
+use rayon::prelude::*;
+use structopt::StructOpt;
+
+use std::path::{Path, PathBuf};
+use std::result;
+
+fn main() -> Result<()> {
+    let opts = Opts::from_args();
+
+    let inputs = inputs(&opts.input, opts.recursive)?;
+
+    if inputs.is_empty() {
+        bail!("No maps found in {}", opts.input.display());
+    }
+
+    let results = inputs
+        .par_iter()
+        .map(|input| (input, validate(input, &opts)))
+        .collect::<Vec<_>>();
+
+    let mut invalid = 0;
+
+    for (input, result) in results {
+        match result {
+            Ok(problems) if problems.is_empty() => {
+                if !opts.quiet {
+                    println!("{}: ok", input.display());
+                }
+            }
+            Ok(problems) => {
+                println!("{}: {} problem(s)", input.display(), problems.len());
+                for problem in problems.iter() {
+                    println!("  {}", problem);
+                }
+                invalid += 1;
+            }
+            Err(e) => {
+                eprintln!("Failed {}: {:#}", input.display(), e);
+                invalid += 1;
+            }
+        }
+    }
+
+    if invalid > 0 {
+        bail!("{} map(s) didn't validate", invalid);
+    }
+
+    Ok(())
+}
+
+type Result<T> = result::Result<T, Error>;
+
+/// Validates a map against its textures. A `.cn2` map uses the textures next
+/// to it, an exported map the ones its paths point to.
+fn validate(input: &Path, opts: &Opts) -> Result<Vec<Problem>> {
+    let format = MapFormat::from_path(input).context("Unknown map format")?;
+
+    if format == MapFormat::Cn2 {
+        let map = Map::load(input, &ParseOptions::default())?;
+
+        Ok(map.validate(Path::new("")))
+    } else {
+        let map = Map::open_without_textures(input, &ParseOptions::default())?;
+
+        Ok(map.validate(&opts.assets))
+    }
+}
+
+#[derive(StructOpt)]
+#[structopt(name = "map-validate", about = "Check maps against the sprite sheets they index into", version = env!("CARGO_PKG_VERSION"))]
+pub struct Opts {
+    /// A map or a directory of `.cn2`, `.ron`, `.json` or `.bin` maps
+    #[structopt(parse(from_os_str))]
+    pub input: PathBuf,
+    /// Search the input directory recursively
+    #[structopt(short, long)]
+    pub recursive: bool,
+    /// Directory relative texture paths of exported maps are resolved against
+    #[structopt(short, long, parse(from_os_str), default_value = "assets")]
+    pub assets: PathBuf,
+    /// Only print maps with problems
+    #[structopt(short, long)]
+    pub quiet: bool,
+}
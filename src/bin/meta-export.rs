@@ -1,272 +1,456 @@
-use anyhow::{bail, Error};
-use byteorder::{ByteOrder, LittleEndian};
-use ron::ser::{to_string_pretty, PrettyConfig};
-use serde::Serialize;
+use anyhow::{bail, Context, Error};
+use cn2::{relative_path, Map, MapFormat, ParseOptions, PathExt, TriggerTable};
+use rayon::prelude::*;
 use structopt::StructOpt;
+use walkdir::WalkDir;
 
+use std::collections::HashMap;
 use std::fs;
-use std::io;
 use std::path::{Path, PathBuf};
 use std::result;
 
 fn main() -> Result<()> {
     let opts = Opts::from_args();
 
-    if !opts.file.is_file() {
-        bail!("<file> must be a valid file");
+    let triggers = match &opts.triggers {
+        Some(path) => TriggerTable::load(path)?,
+        None => TriggerTable::default(),
+    };
+
+    let options = ParseOptions {
+        lenient: opts.lenient,
+        triggers,
+    };
+
+    let jobs = collect_jobs(&opts)?;
+
+    if jobs.is_empty() {
+        bail!(
+            "No `.{}` files found in {}",
+            opts.input_ext(),
+            opts.input.display()
+        );
     }
 
-    let tileset = Tileset::load(&opts.file)?;
+    let outcomes = jobs
+        .into_par_iter()
+        .map(|job| {
+            let outcome = job.run(&opts, &options);
+            (job.input, outcome)
+        })
+        .collect::<Vec<_>>();
 
-    let pretty = PrettyConfig {
-        depth_limit: 6,
-        separate_tuple_members: true,
-        enumerate_arrays: true,
-        ..Default::default()
-    };
-    let s = to_string_pretty(&tileset, pretty).expect("Serialization failed");
+    let failed = print_summary(&outcomes);
 
-    let out_stem = opts.file.file_stem_str();
-    fs::write(format!("output/{}.ron", out_stem), s)?;
+    if failed > 0 {
+        bail!("{} file(s) failed to export", failed);
+    }
 
     Ok(())
 }
 
-type Result<T> = result::Result<T, Error>;
-
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize)]
-enum TextureKind {
-    Base,
-    Var,
-    Anm,
+/// A single file to convert and where its output goes.
+struct Job {
+    input: PathBuf,
+    output: PathBuf,
+    skip: Option<String>,
 }
 
-impl TextureKind {
-    fn make(index: usize) -> Result<TextureKind> {
-        match index {
-            0 => Ok(TextureKind::Base),
-            1 => Ok(TextureKind::Var),
-            2 => Ok(TextureKind::Anm),
-            _ => bail!("Invalid tileset index"),
-        }
-    }
+/// Things worth pointing out about an exported map.
+#[derive(Default)]
+struct Notes {
+    /// Anomalies tolerated in lenient mode.
+    anomalies: usize,
+    /// Bytes after the trigger block.
+    trailing: usize,
+    /// Cells with unknown bits set in their kind byte.
+    kind_bits: usize,
 }
 
-#[derive(Debug, Copy, Clone, Serialize)]
-enum TriggerKind {
-    Uninitialized,
-    Passable,
-    Blocker,
-    UpperLowerDelta,
-    LowerUpperDelta,
-    Hidden,
-    Bridge,
-    Damage,
-    BottomTransparent,
-    BottomHidden,
-    Unknown7,
-    Unknown12,
-    Unknown13,
-    Treasure(u8),
-    Exit(u8),
-    Unknown(u8),
-}
+impl Notes {
+    fn describe(&self) -> Vec<String> {
+        let mut notes = vec![];
 
-impl TriggerKind {
-    fn new(v: u8) -> TriggerKind {
-        match v {
-            0x00 => TriggerKind::Passable,
-            0x01 => TriggerKind::Blocker,
-            0x02 => TriggerKind::UpperLowerDelta,
-            0x03 => TriggerKind::LowerUpperDelta,
-            0x04 => TriggerKind::Hidden,
-            0x05 => TriggerKind::Bridge,
-            0x06 => TriggerKind::Damage,
-            0x10 => TriggerKind::BottomTransparent,
-            0x11 => TriggerKind::BottomHidden,
-            0x07 => TriggerKind::Unknown7,
-            0x12 => TriggerKind::Unknown12,
-            0x13 => TriggerKind::Unknown13,
-            0x20..=0x3F => TriggerKind::Treasure(v & 0x3F),
-            0x40..=0x5F => TriggerKind::Exit(v & 0x3F),
-            n => TriggerKind::Unknown(n),
+        if self.anomalies > 0 {
+            notes.push(format!("{} anomalies", self.anomalies));
         }
-    }
-}
 
-#[derive(Debug, Serialize)]
-struct Cell {
-    index: u8,
-    kind: TextureKind,
-    trigger: TriggerKind,
-}
+        if self.trailing > 0 {
+            notes.push(format!("{} trailing bytes kept", self.trailing));
+        }
 
-#[derive(Debug, Serialize)]
-struct Layer {
-    cells: Vec<Cell>,
-}
+        if self.kind_bits > 0 {
+            notes.push(format!(
+                "{} cells with unknown kind bits kept",
+                self.kind_bits
+            ));
+        }
 
-#[derive(Debug, Serialize)]
-struct Tileset {
-    width: usize,
-    height: usize,
-    base: PathBuf,
-    var: PathBuf,
-    anm: PathBuf,
-    layers: Vec<Layer>,
+        notes
+    }
 }
 
-impl Tileset {
-    fn load(path: impl AsRef<Path>) -> Result<Tileset> {
-        let mut meta = fs::File::open(&path)?;
-
-        let (base, var, anm) = parse_texture_paths(path)?;
+enum Outcome {
+    Exported(PathBuf, Notes),
+    Skipped(String),
+    Failed(Error),
+}
 
-        let width = meta.read_u16()? as _;
-        let height = meta.read_u16()? as _;
+impl Job {
+    fn run(&self, opts: &Opts, options: &ParseOptions) -> Outcome {
+        if let Some(reason) = &self.skip {
+            return Outcome::Skipped(reason.clone());
+        }
 
-        let mut layers = build_layers(&mut meta, width, height);
+        if opts.skip_existing && self.output.exists() {
+            return Outcome::Skipped("output already exists".into());
+        }
 
-        add_triggers(&mut meta, &mut layers, width, height);
+        let result = if opts.reverse {
+            import(&self.input, &self.output, opts.format, options).map(|_| Notes::default())
+        } else {
+            export(&self.input, &self.output, opts, options)
+        };
 
-        Ok(Tileset {
-            width,
-            height,
-            base,
-            var,
-            anm,
-            layers,
-        })
+        match result {
+            Ok(notes) => Outcome::Exported(self.output.clone(), notes),
+            Err(e) => Outcome::Failed(e),
+        }
     }
 }
 
-trait ReadExt: io::Read {
-    fn read_u16(&mut self) -> Result<u16> {
-        let mut buf = [0; 2];
-        self.read_exact(&mut buf)?;
+fn export(input: &Path, output: &Path, opts: &Opts, options: &ParseOptions) -> Result<Notes> {
+    let mut map = Map::load(input, options)?;
+
+    if opts.validate {
+        let problems = map.validate(Path::new(""));
+
+        if !problems.is_empty() {
+            bail!(
+                "{} validation problem(s):\n    {}",
+                problems.len(),
+                problems
+                    .iter()
+                    .map(|problem| problem.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n    ")
+            );
+        }
+    }
 
-        Ok(LittleEndian::read_u16(&buf))
+    if opts.verify {
+        verify_round_trip(&map, input, &options.triggers)?;
     }
-}
 
-impl<R> ReadExt for R where R: io::Read {}
+    if let Some(asset_root) = &opts.asset_root {
+        relocate_textures(&mut map, input, asset_root, opts)?;
+    }
 
-fn build_layers<R: ReadExt>(meta: &mut R, width: usize, height: usize) -> Vec<Layer> {
-    let cell_count = width * height;
+    let encoded = map
+        .encode(opts.format, &options.triggers)
+        .context("Serialization failed")?;
+
+    write_output(output, encoded)?;
+
+    Ok(Notes {
+        anomalies: map.anomalies.len(),
+        trailing: map.trailing.len(),
+        kind_bits: map
+            .layers
+            .iter()
+            .flat_map(|layer| layer.cells.iter())
+            .filter(|cell| cell.raw_kind.is_some())
+            .count(),
+    })
+}
 
-    (0..2)
-        .map(|_| {
-            let mut buf = vec![0; cell_count * 2];
-            let _ = meta.read_exact(&mut buf);
+/// Rewrites the texture paths of `map` relative to `asset_root`, first copying
+/// the textures to `<asset_root>/tilesets/<map>/` with --copy-textures.
+fn relocate_textures(map: &mut Map, input: &Path, asset_root: &Path, opts: &Opts) -> Result<()> {
+    let copy = opts.copy_textures;
 
-            let cells = (0..cell_count)
-                .map(|i| Cell {
-                    index: buf[i * 2],
-                    kind: TextureKind::make(buf[i * 2 + 1] as usize).unwrap(),
-                    trigger: TriggerKind::Uninitialized,
-                })
-                .collect();
+    let tileset_dir = asset_root
+        .join("tilesets")
+        .join(relative_dir(opts, input))
+        .join(input.file_stem_str());
 
-            Layer { cells }
-        })
-        .collect()
-}
+    if copy {
+        fs::create_dir_all(&tileset_dir)
+            .with_context(|| format!("Couldn't create {}", tileset_dir.display()))?;
+    }
+
+    let asset_root = fs::canonicalize(asset_root)
+        .with_context(|| format!("Couldn't find asset root {}", asset_root.display()))?;
 
-fn add_triggers<R: ReadExt>(meta: &mut R, layers: &mut Vec<Layer>, width: usize, height: usize) {
-    let cell_count = width * height;
+    for path in [&mut map.base, &mut map.var, &mut map.anm].iter_mut() {
+        if copy {
+            let dest = tileset_dir.join(path.file_name_str());
 
-    let mut buf = vec![0; cell_count * 2];
-    let _ = meta.read_exact(&mut buf);
+            fs::copy(&path, &dest).with_context(|| {
+                format!("Couldn't copy {} to {}", path.display(), dest.display())
+            })?;
 
-    for layer in layers.iter_mut() {
-        for (idx, cell) in layer.cells.iter_mut().enumerate() {
-            cell.trigger = TriggerKind::new(buf[idx]);
+            **path = dest;
         }
+
+        let absolute = fs::canonicalize(&path)
+            .with_context(|| format!("Couldn't find texture {}", path.display()))?;
+
+        **path = relative_path(&absolute, &asset_root).with_context(|| {
+            format!(
+                "{} can't be made relative to {}",
+                absolute.display(),
+                asset_root.display()
+            )
+        })?;
     }
+
+    Ok(())
 }
 
-fn parse_texture_paths(path: impl AsRef<Path>) -> Result<(PathBuf, PathBuf, PathBuf)> {
-    let (mut base, mut var, mut anm) = (None, None, None);
+/// Encodes an exported map back into a `.cn2` file.
+fn import(input: &Path, output: &Path, format: MapFormat, options: &ParseOptions) -> Result<()> {
+    let map = Map::decode(&fs::read(input)?, format, options).context("Deserialization failed")?;
 
-    let meta_name = path.file_name_str();
+    write_output(output, map.to_bytes(&options.triggers)?)
+}
 
-    let identifier: &str = if let Some(ident) = meta_name.split('_').next() {
-        ident
-    } else {
-        bail!("Couldn't parse identifier from `.cn2` file name");
-    };
+/// Checks that encoding `map` reproduces the `.cn2` file it was parsed from.
+fn verify_round_trip(map: &Map, input: &Path, triggers: &TriggerTable) -> Result<()> {
+    let original = fs::read(input)?;
+    let encoded = map.to_bytes(triggers)?;
+
+    if let Some(offset) = original.iter().zip(&encoded).position(|(a, b)| a != b) {
+        bail!(
+            "Round trip differs from the source at offset {:#x}: expected {:#04x}, encoded {:#04x}",
+            offset,
+            original[offset],
+            encoded[offset]
+        );
+    }
 
-    let dir = if let Some(p) = path.as_ref().parent() {
-        p
-    } else {
-        bail!("No parent directory found for supplied `.cn2` file");
-    };
+    if original.len() != encoded.len() {
+        bail!(
+            "Round trip encoded {} bytes, but the source has {}",
+            encoded.len(),
+            original.len()
+        );
+    }
 
-    let read_dir = fs::read_dir(&dir)?;
+    Ok(())
+}
 
-    for maybe_entry in read_dir {
-        if let Ok(entry) = maybe_entry {
-            let entry_path = entry.path();
+fn write_output(output: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    if let Some(dir) = output.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Couldn't create output directory {}", dir.display()))?;
+    }
+    fs::write(output, contents).with_context(|| format!("Couldn't write {}", output.display()))?;
 
-            let entry_ext = entry_path.ext_str().to_lowercase();
-            let entry_stem = entry_path.file_stem_str().to_lowercase();
+    Ok(())
+}
 
-            if entry_stem.starts_with(&format!("{}_", identifier)) && entry_ext == "png" {
-                if entry_stem.ends_with("_base") {
-                    base = Some(entry_path);
-                } else if entry_stem.ends_with("_var") {
-                    var = Some(entry_path);
-                } else if entry_stem.ends_with("_anm") {
-                    anm = Some(entry_path);
-                }
+/// Finds every file to convert and decides where each one is written.
+fn collect_jobs(opts: &Opts) -> Result<Vec<Job>> {
+    let inputs = if opts.input.is_file() {
+        vec![opts.input.clone()]
+    } else if opts.input.is_dir() {
+        let max_depth = if opts.recursive { usize::MAX } else { 1 };
+
+        let mut inputs = vec![];
+
+        for entry in WalkDir::new(&opts.input).max_depth(max_depth) {
+            let entry = entry?;
+
+            if entry.file_type().is_file()
+                && entry
+                    .path()
+                    .ext_str()
+                    .eq_ignore_ascii_case(opts.input_ext())
+            {
+                inputs.push(entry.into_path());
             }
         }
-    }
 
-    if base.is_none() || var.is_none() || anm.is_none() {
-        bail!("Couldn't find all related _base, _var and _anm textures");
-    }
+        inputs.sort();
+        inputs
+    } else {
+        bail!("<input> must be a valid file or directory");
+    };
 
-    Ok((base.unwrap(), var.unwrap(), anm.unwrap()))
+    let mut claimed: HashMap<PathBuf, PathBuf> = HashMap::new();
+
+    let jobs = inputs
+        .into_iter()
+        .map(|input| {
+            let output = output_path(opts, &input);
+
+            let skip = if let Some(other) = claimed.get(&output) {
+                Some(format!(
+                    "output {} collides with {}, use --mirror to keep both",
+                    output.display(),
+                    other.display()
+                ))
+            } else {
+                claimed.insert(output.clone(), input.clone());
+                None
+            };
+
+            Job {
+                input,
+                output,
+                skip,
+            }
+        })
+        .collect();
+
+    Ok(jobs)
 }
 
-trait PathExt {
-    fn file_name_str(&self) -> &str;
+fn output_path(opts: &Opts, input: &Path) -> PathBuf {
+    let file_name = format!("{}.{}", input.file_stem_str(), opts.output_ext());
 
-    fn file_stem_str(&self) -> &str;
+    opts.out_dir.join(relative_dir(opts, input)).join(file_name)
+}
 
-    fn ext_str(&self) -> &str;
+/// The directory of `input` relative to <input> with --mirror, otherwise empty.
+fn relative_dir<'a>(opts: &Opts, input: &'a Path) -> &'a Path {
+    if opts.mirror {
+        input
+            .parent()
+            .and_then(|dir| dir.strip_prefix(&opts.input).ok())
+            .unwrap_or_else(|| Path::new(""))
+    } else {
+        Path::new("")
+    }
 }
 
-impl<P: AsRef<Path>> PathExt for P {
-    fn file_name_str(&self) -> &str {
-        self.as_ref()
-            .file_name()
-            .unwrap_or_default()
-            .to_str()
-            .unwrap_or_default()
+/// Prints exported, skipped and failed files, returning the number of failures.
+fn print_summary(outcomes: &[(PathBuf, Outcome)]) -> usize {
+    let exported = outcomes
+        .iter()
+        .filter_map(|(input, outcome)| match outcome {
+            Outcome::Exported(output, notes) => Some((input, output, notes)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let skipped = outcomes
+        .iter()
+        .filter_map(|(input, outcome)| match outcome {
+            Outcome::Skipped(reason) => Some((input, reason)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let failed = outcomes
+        .iter()
+        .filter_map(|(input, outcome)| match outcome {
+            Outcome::Failed(error) => Some((input, error)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    if !exported.is_empty() {
+        println!("Exported {} file(s):", exported.len());
+        for (input, output, notes) in exported.iter() {
+            let notes = notes.describe();
+
+            if !notes.is_empty() {
+                println!(
+                    "  {} -> {} ({})",
+                    input.display(),
+                    output.display(),
+                    notes.join(", ")
+                );
+            } else {
+                println!("  {} -> {}", input.display(), output.display());
+            }
+        }
     }
 
-    fn file_stem_str(&self) -> &str {
-        self.as_ref()
-            .file_stem()
-            .unwrap_or_default()
-            .to_str()
-            .unwrap_or_default()
+    if !skipped.is_empty() {
+        println!("Skipped {} file(s):", skipped.len());
+        for (input, reason) in skipped.iter() {
+            println!("  {}: {}", input.display(), reason);
+        }
     }
 
-    fn ext_str(&self) -> &str {
-        self.as_ref()
-            .extension()
-            .unwrap_or_default()
-            .to_str()
-            .unwrap_or_default()
+    if !failed.is_empty() {
+        eprintln!("Failed {} file(s):", failed.len());
+        for (input, error) in failed.iter() {
+            eprintln!("  {}: {:#}", input.display(), error);
+        }
     }
+
+    failed.len()
 }
 
+type Result<T> = result::Result<T, Error>;
+
 #[derive(StructOpt)]
-#[structopt(name = "meta-export", about = "Export CN2 tileset metadata to `.ron` format", version = env!("CARGO_PKG_VERSION"))]
+#[structopt(name = "meta-export", about = "Export CN2 tileset metadata to `.ron`, `.json` or compact `.bin` format, or encode it back with --reverse", version = env!("CARGO_PKG_VERSION"))]
 pub struct Opts {
+    /// A `.cn2` file, or a directory containing `.cn2` files (exported maps with --reverse)
     #[structopt(parse(from_os_str))]
-    pub file: PathBuf,
+    pub input: PathBuf,
+    /// Directory the exported files are written to
+    #[structopt(short, long, parse(from_os_str), default_value = "output")]
+    pub out_dir: PathBuf,
+    /// Format of the exported files, or of the files to encode with --reverse
+    #[structopt(short, long, default_value = "ron", possible_values = &["ron", "json", "bin"])]
+    pub format: MapFormat,
+    /// Also search subdirectories of <input>
+    #[structopt(short, long)]
+    pub recursive: bool,
+    /// Mirror the directory layout of <input> inside <out-dir>
+    #[structopt(short, long)]
+    pub mirror: bool,
+    /// Skip files whose output already exists
+    #[structopt(long)]
+    pub skip_existing: bool,
+    /// Write texture paths relative to this directory, usually the game's `assets`
+    #[structopt(short, long, parse(from_os_str))]
+    pub asset_root: Option<PathBuf>,
+    /// Copy each map's textures to `<asset-root>/tilesets/<map>/`, mirroring the
+    /// layout of <input> with --mirror
+    #[structopt(long, requires = "asset-root")]
+    pub copy_textures: bool,
+    /// Record truncated or invalid data in the output instead of failing, or
+    /// tolerate invalid texture kinds of `.bin` files with --reverse
+    #[structopt(long)]
+    pub lenient: bool,
+    /// Check that re-encoding each map reproduces its `.cn2` file byte for byte
+    #[structopt(long)]
+    pub verify: bool,
+    /// Check the textures are 16x16 grids of 32px tiles and every cell's tile
+    /// fits its texture, failing maps that don't
+    #[structopt(long)]
+    pub validate: bool,
+    /// A RON trigger table to decode trigger bytes with, or to encode them with
+    /// --reverse. See `cn2/triggers.ron`
+    #[structopt(short, long, parse(from_os_str))]
+    pub triggers: Option<PathBuf>,
+    /// Encode exported files back into `.cn2`
+    #[structopt(
+        long,
+        conflicts_with_all = &["verify", "validate", "asset-root"]
+    )]
+    pub reverse: bool,
+}
+
+impl Opts {
+    fn input_ext(&self) -> &'static str {
+        if self.reverse {
+            self.format.extension()
+        } else {
+            "cn2"
+        }
+    }
+
+    fn output_ext(&self) -> &'static str {
+        if self.reverse {
+            "cn2"
+        } else {
+            self.format.extension()
+        }
+    }
 }
@@ -2,8 +2,7 @@ use amethyst::{
     ecs::{Component, DenseVecStorage, NullStorage},
     renderer::{palette::Srgba, resources::Tint},
 };
-
-use serde::{Deserialize, Serialize};
+use cn2::TriggerKind;
 
 #[derive(Component, Default)]
 #[storage(NullStorage)]
@@ -13,39 +12,17 @@ pub struct UpperTile;
 #[storage(NullStorage)]
 pub struct LowerTile;
 
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, Component)]
+/// The `TriggerKind` of a map tile.
+#[derive(Clone, Copy, Debug, PartialEq, Component)]
 #[storage(DenseVecStorage)]
-pub enum TriggerKind {
-    Uninitialized,
-    Passable,
-    Blocker,
-    UpperLowerDelta,
-    LowerUpperDelta,
-    Hidden,
-    Bridge,
-    Damage,
-    BottomTransparent,
-    BottomHidden,
-    Unknown7,
-    Unknown12,
-    Unknown13,
-    Treasure(u8),
-    Exit(u8),
-    Unknown(u8),
-}
+pub struct Trigger(pub TriggerKind);
 
-impl TriggerKind {
+impl Trigger {
     pub fn tint(&self) -> Tint {
-        match self {
+        match self.0 {
             TriggerKind::Passable => Tint(Srgba::from_components((0.6, 1.0, 0.6, 1.0))),
             TriggerKind::Blocker => Tint(Srgba::from_components((1.0, 0.6, 0.6, 1.0))),
             _ => Tint(Srgba::from_components((1.0, 1.0, 1.0, 1.0))),
         }
     }
 }
-
-impl Default for TriggerKind {
-    fn default() -> Self {
-        TriggerKind::Uninitialized
-    }
-}
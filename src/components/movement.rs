@@ -5,13 +5,22 @@ use amethyst::ecs::{Component, DenseVecStorage};
 pub struct Movement {
     pub run_count: u8,
     //pub turn_count: u8,
+    /// Pixels moved per fixed update while running, dividing the 32px of a
+    /// tile.
+    pub speed: u8,
 }
 
-impl Default for Movement {
-    fn default() -> Self {
+impl Movement {
+    pub fn new(speed: u8) -> Self {
         Self {
             run_count: 0,
             //turn_count: 0,
+            speed,
         }
     }
+
+    /// Fixed updates it takes to run across a tile.
+    pub fn steps(&self) -> u8 {
+        32 / self.speed
+    }
 }
@@ -17,17 +17,35 @@ use crate::{
     resources::map::Map,
 };
 
-pub fn load_player_one(world: &mut World, map: &Map, prefab: Handle<Prefab<AnimationPrefabData>>) {
+/// Spawns the player on the `spawn` cell of `map`, or in the middle of the map
+/// without one, running `speed` pixels per fixed update.
+pub fn load_player_one(
+    world: &mut World,
+    map: &Map,
+    spawn: Option<(usize, usize)>,
+    speed: u8,
+    prefab: Handle<Prefab<AnimationPrefabData>>,
+) {
     let mut transform = Transform::default();
 
-    let x_offset = if map.width % 2 == 0 { 16.0 } else { 0.0 };
-    let y_offset = if map.height % 2 == 0 { 16.0 } else { 0.0 };
+    match spawn {
+        // Same position as the tile of the cell, see `Map::load_layer`
+        Some((x, y)) => transform.set_translation_xyz(
+            x as f32 * 32.0 + 16.0,
+            (map.width as f32 * 32.0) - (y as f32 * 32.0 + 16.0),
+            -1.0,
+        ),
+        None => {
+            let x_offset = if map.width % 2 == 0 { 16.0 } else { 0.0 };
+            let y_offset = if map.height % 2 == 0 { 16.0 } else { 0.0 };
 
-    transform.set_translation_xyz(
-        (map.width as f32 * 32.0) / 2.0 + x_offset,
-        (map.height as f32 * 32.0) / 2.0 + y_offset,
-        -1.0,
-    );
+            transform.set_translation_xyz(
+                (map.width as f32 * 32.0) / 2.0 + x_offset,
+                (map.height as f32 * 32.0) / 2.0 + y_offset,
+                -1.0,
+            )
+        }
+    };
 
     world
         .create_entity()
@@ -47,7 +65,7 @@ pub fn load_player_one(world: &mut World, map: &Map, prefab: Handle<Prefab<Anima
         .with(prefab)
         .with(Transparent) // Necessary for ordered layering
         .with(Direction::default())
-        .with(Movement::default())
+        .with(Movement::new(speed))
         .with(Removal::new(0usize))
         .build();
 }
@@ -57,6 +75,7 @@ pub fn reload_player_one(
     entity: Entity,
     x: f32,
     y: f32,
+    speed: u8,
     prefab: Handle<Prefab<AnimationPrefabData>>,
 ) {
     let mut transform = Transform::default();
@@ -82,6 +101,6 @@ pub fn reload_player_one(
     updater.insert(entity, prefab);
     updater.insert(entity, Transparent);
     updater.insert(entity, Direction::default());
-    updater.insert(entity, Movement::default());
+    updater.insert(entity, Movement::new(speed));
     updater.insert(entity, Removal::new(0usize));
 }
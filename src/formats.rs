@@ -0,0 +1,2 @@
+mod map;
+pub use map::{Cn2Format, ExportedMapFormat};
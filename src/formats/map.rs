@@ -0,0 +1,87 @@
+use amethyst::{
+    assets::{Format, FormatValue, Source},
+    Error,
+};
+use cn2::{DirResolver, MapFormat, ParseOptions, TextureResolver, Textures};
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::resources::map::Map;
+
+/// Loads a `Map` straight from a `.cn2` file. The textures are discovered next
+/// to the file in the asset directory, the same way `meta-export` finds them.
+#[derive(Clone, Debug)]
+pub struct Cn2Format {
+    textures: DirResolver,
+}
+
+impl Cn2Format {
+    pub fn new(assets_dir: impl Into<PathBuf>) -> Self {
+        Cn2Format {
+            textures: DirResolver::new(assets_dir),
+        }
+    }
+
+    /// Texture paths are loaded through the asset loader, so make them relative
+    /// to the asset directory when possible.
+    fn asset_path(&self, path: &Path) -> PathBuf {
+        path.strip_prefix(&self.textures.dir)
+            .unwrap_or(path)
+            .to_path_buf()
+    }
+}
+
+impl Format<Map> for Cn2Format {
+    fn name(&self) -> &'static str {
+        "CN2"
+    }
+
+    fn import(
+        &self,
+        name: String,
+        source: Arc<dyn Source>,
+        _create_reload: Option<Box<dyn Format<Map>>>,
+    ) -> Result<FormatValue<Map>, Error> {
+        let bytes = source.load(&name)?;
+
+        let resolver = |name: &Path| -> cn2::Result<Textures> {
+            let found = self.textures.resolve(name)?;
+
+            Ok(Textures {
+                base: self.asset_path(&found.base),
+                var: self.asset_path(&found.var),
+                anm: self.asset_path(&found.anm),
+            })
+        };
+
+        let map = cn2::Map::parse(&bytes, &name, &resolver, &ParseOptions::default())?;
+
+        Ok(FormatValue::data(Map(map)))
+    }
+}
+
+/// Loads a `Map` exported by `meta-export` as `.ron`, `.json` or compact `.bin`.
+#[derive(Clone, Debug)]
+pub struct ExportedMapFormat(pub MapFormat);
+
+impl Format<Map> for ExportedMapFormat {
+    fn name(&self) -> &'static str {
+        match self.0 {
+            MapFormat::Cn2 => "CN2",
+            MapFormat::Ron => "RON",
+            MapFormat::Json => "JSON",
+            MapFormat::Bin => "BIN",
+        }
+    }
+
+    fn import_simple(&self, bytes: Vec<u8>) -> Result<Map, Error> {
+        Ok(Map(cn2::Map::decode(
+            &bytes,
+            self.0,
+            &ParseOptions::default(),
+        )?))
+    }
+}
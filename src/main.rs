@@ -4,7 +4,7 @@
 use amethyst::{
     animation::AnimationBundle,
     assets::{PrefabLoaderSystemDesc, Processor},
-    audio::{AudioBundle, DjSystemDesc},
+    config::Config,
     core::transform::TransformBundle,
     input::{InputBundle, StringBindings},
     renderer::{
@@ -19,10 +19,18 @@ use amethyst::{
 };
 
 use components::animation::{AnimationId, AnimationPrefabData};
-use resources::{asset::Music, map::Map};
+use resources::{
+    asset::AssetsDir,
+    catalog::MapCatalog,
+    game::Game,
+    map::Map,
+    music::{LoopTable, Music},
+    roster::Roster,
+};
 
 mod components;
 mod entities;
+mod formats;
 mod resources;
 mod states;
 mod systems;
@@ -35,6 +43,16 @@ fn main() -> amethyst::Result<()> {
     let bindings_path = root.join("resources/bindings.ron");
     let assets_path = root.join("assets");
 
+    let catalog = MapCatalog::load(assets_path.join("maps.ron"))?;
+    catalog
+        .check(&assets_path)
+        .map_err(amethyst::Error::from_string)?;
+
+    let roster = Roster::load(assets_path.join("characters.ron"))?;
+    roster.check().map_err(amethyst::Error::from_string)?;
+
+    let loops = LoopTable::load(assets_path.join("audio/loops.ron"))?;
+
     let prefab_loader_system_desc = PrefabLoaderSystemDesc::<AnimationPrefabData>::default();
 
     let game_data = GameDataBuilder::default()
@@ -56,18 +74,16 @@ fn main() -> amethyst::Result<()> {
         .with_bundle(TransformBundle::new().with_dep(&["sampler_interpolation_system"]))?
         .with_bundle(InputBundle::<StringBindings>::new().with_bindings_from_file(bindings_path)?)?
         .with_bundle(UiBundle::<StringBindings>::new())?
-        .with_bundle(AudioBundle::default())?
-        .with_system_desc(
-            DjSystemDesc::new(|music: &mut Music| music.music.next()),
-            "dj_system",
-            &[],
-        )
+        .with(systems::MusicSystem, "music_system", &[])
         .with(Processor::<Map>::new(), "map_processor", &[]);
 
     let mut state = states::LoadState::default();
     state.first_load = true;
 
-    let mut game = Application::build(assets_path, state)?
+    let mut game = Application::build(&assets_path, state)?
+        .with_resource(AssetsDir(assets_path))
+        .with_resource(Game::new(catalog, roster))
+        .with_resource(Music::new(loops))
         //.with_frame_limit(FrameRateLimitStrategy::Sleep, 60)
         .build(game_data)?;
 
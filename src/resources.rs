@@ -1,4 +1,7 @@
 pub mod asset;
+pub mod catalog;
 pub mod game;
 pub mod map;
+pub mod music;
+pub mod roster;
 pub mod sprites;
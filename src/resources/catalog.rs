@@ -0,0 +1,69 @@
+use cn2::ParseOptions;
+use serde::{Deserialize, Serialize};
+
+use std::path::Path;
+
+use crate::resources::asset::Audio;
+
+/// The maps the game cycles through, read from `assets/maps.ron`. It's kept
+/// out of `assets/maps` so the tools scanning that directory only find maps.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct MapCatalog {
+    pub maps: Vec<MapEntry>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MapEntry {
+    /// Path of the map, relative to the assets directory.
+    pub path: String,
+    pub name: String,
+    /// The location the map is part of, e.g. `Baron Castle`.
+    pub group: String,
+    /// Floor of the map within its group.
+    #[serde(default)]
+    pub floor: i32,
+    /// Music played on the map.
+    #[serde(default)]
+    pub bgm: Option<Audio>,
+    /// The cell the player starts on, counted from the top left. The middle of
+    /// the map if not given.
+    #[serde(default)]
+    pub spawn: Option<(usize, usize)>,
+}
+
+impl MapCatalog {
+    /// Checks there's at least one map and every spawn is a cell of its map.
+    /// Maps with a spawn are opened from `assets_dir` to find their size.
+    pub fn check(&self, assets_dir: &Path) -> Result<(), String> {
+        if self.maps.is_empty() {
+            return Err("The map catalog doesn't list any maps".to_string());
+        }
+
+        for entry in self.maps.iter() {
+            let (x, y) = match entry.spawn {
+                Some(spawn) => spawn,
+                None => continue,
+            };
+
+            let map = cn2::Map::open_without_textures(
+                assets_dir.join(&entry.path),
+                &ParseOptions::default(),
+            )
+            .map_err(|e| {
+                format!(
+                    "Can't check the spawn of `{}`, {} doesn't open: {}",
+                    entry.name, entry.path, e
+                )
+            })?;
+
+            if map.cell_index(x, y).is_none() {
+                return Err(format!(
+                    "Spawn ({}, {}) of `{}` is outside the {}x{} map {}",
+                    x, y, entry.name, map.width, map.height, entry.path
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
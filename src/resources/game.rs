@@ -1,11 +1,13 @@
-use crate::resources::asset::{AssetType, Audio, Character};
+use crate::resources::{
+    catalog::{MapCatalog, MapEntry},
+    roster::{CharacterEntry, Roster},
+};
 
 pub struct Game {
     pub load_map: Option<usize>,
-    pub maps: [&'static str; 5],
+    pub maps: Vec<MapEntry>,
     pub load_char: bool,
-    pub chars: [AssetType; 7],
-    pub music: [AssetType; 2],
+    pub chars: Vec<CharacterEntry>,
     pub current_map: usize,
     pub current_char: usize,
     pub map_width: f32,
@@ -14,39 +16,33 @@ pub struct Game {
     pub button_pressed: bool,
 }
 
-impl Default for Game {
-    fn default() -> Self {
+impl Game {
+    /// Starts on the first map and character. `catalog` and `roster` must
+    /// have passed their checks, so neither is empty.
+    pub fn new(catalog: MapCatalog, roster: Roster) -> Self {
         Game {
             current_map: 0,
             current_char: 0,
             load_map: None,
-            maps: [
-                "maps/castle1_baron_castle_01.ron",
-                "maps/castle2_dwarven_01.ron",
-                "maps/dtown_agart_01.ron",
-                "maps/ship_adamant_forest_01.ron",
-                "maps/town_mythril_01.ron",
-            ],
+            maps: catalog.maps,
             load_char: false,
-            chars: [
-                AssetType::Character(Character::Cain),
-                AssetType::Character(Character::Cecil),
-                AssetType::Character(Character::Kyuucecil),
-                AssetType::Character(Character::Kyuurydia),
-                AssetType::Character(Character::Roza),
-                AssetType::Character(Character::Rydia),
-                AssetType::Character(Character::Yang),
-            ],
-            music: [
-                AssetType::Audio(Audio::BgmAi),
-                AssetType::Audio(Audio::BgmPrologue),
-            ],
+            chars: roster.characters,
             map_width: 0.0,
             map_height: 0.0,
             debug_mode: DebugMode::default(),
             button_pressed: false,
         }
     }
+
+    /// The catalog entry of the map being shown.
+    pub fn map_entry(&self) -> &MapEntry {
+        &self.maps[self.current_map]
+    }
+
+    /// The roster entry of the character being played.
+    pub fn character(&self) -> &CharacterEntry {
+        &self.chars[self.current_char]
+    }
 }
 
 #[derive(Debug)]
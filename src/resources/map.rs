@@ -3,7 +3,7 @@ use amethyst::{
         get_animation_set, Animation, AnimationCommand, AnimationControlSet, EndControl,
         InterpolationFunction, Sampler, SpriteRenderChannel, SpriteRenderPrimitive,
     },
-    assets::{Asset, AssetStorage, Handle},
+    assets::{Asset, AssetStorage, Handle, Loader, ProgressCounter},
     core::Transform,
     ecs::{prelude::World, VecStorage},
     prelude::{Builder, WorldExt},
@@ -13,39 +13,23 @@ use amethyst::{
     },
     utils::removal::Removal,
 };
+use cn2::{Layer, MapFormat, TextureKind};
 use serde::{Deserialize, Serialize};
 
-use std::{collections::HashMap, path::PathBuf};
-
-use crate::components::{
-    animation::AnimationId,
-    map::{LowerTile, TriggerKind, UpperTile},
+use std::{
+    collections::HashMap,
+    ops::Deref,
+    path::{Path, PathBuf},
 };
 
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, Eq, Hash)]
-pub enum TextureKind {
-    Base,
-    Var,
-    Anm,
-}
-
-impl Default for TextureKind {
-    fn default() -> Self {
-        TextureKind::Base
-    }
-}
-
-#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
-struct Cell {
-    index: u8,
-    kind: TextureKind,
-    trigger: TriggerKind,
-}
-
-#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
-struct Layer {
-    cells: Vec<Cell>,
-}
+use crate::{
+    components::{
+        animation::AnimationId,
+        map::{LowerTile, Trigger, UpperTile},
+    },
+    formats::{Cn2Format, ExportedMapFormat},
+    resources::asset::AssetsDir,
+};
 
 #[derive(Default)]
 pub struct MapSpriteSheets {
@@ -62,14 +46,17 @@ impl MapSpriteSheets {
     }
 }
 
+/// A `cn2::Map` loaded as an asset.
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
-pub struct Map {
-    pub width: usize,
-    pub height: usize,
-    pub base: PathBuf,
-    pub var: PathBuf,
-    pub anm: PathBuf,
-    layers: Vec<Layer>,
+#[serde(transparent)]
+pub struct Map(pub cn2::Map);
+
+impl Deref for Map {
+    type Target = cn2::Map;
+
+    fn deref(&self) -> &cn2::Map {
+        &self.0
+    }
 }
 
 impl Asset for Map {
@@ -78,7 +65,43 @@ impl Asset for Map {
     type HandleStorage = VecStorage<Handle<Map>>;
 }
 
+/// Loads a `Map`, choosing the format from the file extension. Anything that
+/// isn't `.cn2`, `.json` or `.bin` is loaded as `.ron`.
+pub fn get_map_handle(
+    world: &World,
+    map_path: &str,
+    progress_counter: &mut ProgressCounter,
+) -> Handle<Map> {
+    let loader = world.read_resource::<Loader>();
+    let map_storage = world.read_resource::<AssetStorage<Map>>();
+
+    match MapFormat::from_path(map_path).unwrap_or(MapFormat::Ron) {
+        MapFormat::Cn2 => {
+            let assets_dir = world.read_resource::<AssetsDir>();
+            let format = Cn2Format::new(&assets_dir.0);
+
+            loader.load(map_path, format, progress_counter, &map_storage)
+        }
+        format => loader.load(
+            map_path,
+            ExportedMapFormat(format),
+            progress_counter,
+            &map_storage,
+        ),
+    }
+}
+
 impl Map {
+    /// The textures of this map that don't exist, with relative paths resolved
+    /// against `assets_dir`.
+    pub fn missing_textures(&self, assets_dir: &Path) -> Vec<PathBuf> {
+        [&self.base, &self.var, &self.anm]
+            .iter()
+            .map(|path| assets_dir.join(path))
+            .filter(|path| !path.is_file())
+            .collect()
+    }
+
     pub fn load_map(&self, world: &mut World) {
         for (idx, layer) in self.layers.iter().enumerate() {
             self.load_layer(world, layer, idx);
@@ -116,7 +139,7 @@ impl Map {
 
             let mut entity_builder = world
                 .create_entity()
-                .with(cell.trigger.clone())
+                .with(Trigger(cell.trigger))
                 .with(transform)
                 .with(render)
                 .with(Transparent)
@@ -132,9 +155,9 @@ impl Map {
 
             if cell.kind == TextureKind::Anm {
                 let render_1 = SpriteRenderPrimitive::SpriteIndex(cell.index as usize);
-                let render_2 = SpriteRenderPrimitive::SpriteIndex((cell.index + 1) as usize);
-                let render_3 = SpriteRenderPrimitive::SpriteIndex((cell.index + 2) as usize);
-                let render_4 = SpriteRenderPrimitive::SpriteIndex((cell.index + 3) as usize);
+                let render_2 = SpriteRenderPrimitive::SpriteIndex(cell.index as usize + 1);
+                let render_3 = SpriteRenderPrimitive::SpriteIndex(cell.index as usize + 2);
+                let render_4 = SpriteRenderPrimitive::SpriteIndex(cell.index as usize + 3);
 
                 let sampler = Sampler {
                     input: vec![0.0, 0.25, 0.50, 0.75],
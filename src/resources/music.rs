@@ -0,0 +1,452 @@
+use amethyst::{
+    assets::{Progress, Tracker},
+    core::ArcThreadPool,
+};
+use log::error;
+use rodio::{Sink, Source};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::Cursor,
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use crate::resources::asset::Audio;
+
+/// Formats music can be decoded from, chosen by file extension.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AudioFormat {
+    Ogg,
+    Wav,
+    Flac,
+    Mp3,
+    /// ATRAC3plus, the format of the game's own music. Needs the `at3` feature.
+    At3,
+}
+
+impl AudioFormat {
+    pub fn from_path(path: &Path) -> Option<AudioFormat> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+
+        match ext.as_str() {
+            "ogg" => Some(AudioFormat::Ogg),
+            "wav" => Some(AudioFormat::Wav),
+            "flac" => Some(AudioFormat::Flac),
+            "mp3" => Some(AudioFormat::Mp3),
+            "at3" => Some(AudioFormat::At3),
+            _ => None,
+        }
+    }
+}
+
+/// A position in a track.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum Position {
+    /// Sample frames from the start, counting each channel once.
+    Samples(u64),
+    Seconds(f64),
+}
+
+impl Position {
+    fn frame(self, sample_rate: u32) -> usize {
+        match self {
+            Position::Samples(frame) => frame as usize,
+            Position::Seconds(seconds) => (seconds * f64::from(sample_rate)).round() as usize,
+        }
+    }
+
+    /// The index of the first sample of the frame in interleaved samples.
+    fn index(self, sample_rate: u32, channels: u16) -> usize {
+        self.frame(sample_rate) * usize::from(channels.max(1))
+    }
+}
+
+/// The looping body of a track. Everything before `start` is an intro that
+/// plays once.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct LoopPoints {
+    pub start: Position,
+    /// Where the body jumps back to `start`, the end of the track if `None`.
+    #[serde(default)]
+    pub end: Option<Position>,
+}
+
+/// The loop points of tracks, read from `assets/audio/loops.ron`. Tracks not
+/// listed use the loop of the `smpl` chunk of RIFF files, or loop whole.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct LoopTable {
+    pub tracks: HashMap<Audio, LoopPoints>,
+}
+
+/// A decoded track, held in memory so the body can loop without a gap.
+pub struct Track {
+    samples: Vec<i16>,
+    channels: u16,
+    sample_rate: u32,
+    /// Interleaved sample the body starts at.
+    loop_start: usize,
+    /// Interleaved sample the body ends before.
+    loop_end: usize,
+}
+
+impl Track {
+    /// Decodes the file at `path`, looping it at `loops`, or else at the loop
+    /// of its `smpl` chunk.
+    pub fn open(path: &Path, loops: Option<LoopPoints>) -> Result<Track, String> {
+        let format = AudioFormat::from_path(path)
+            .ok_or("unsupported format, expected .ogg, .wav, .flac, .mp3 or .at3")?;
+
+        let bytes = fs::read(path).map_err(|e| e.to_string())?;
+        let loops = loops.or_else(|| riff_loop(&bytes));
+
+        let (channels, sample_rate, samples) = match format {
+            AudioFormat::At3 => decode_at3(bytes)?,
+            // Rodio tells the formats it was built with apart by itself
+            _ => {
+                let decoder = rodio::Decoder::new(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+                collect(decoder)
+            }
+        };
+
+        let to_index =
+            |position: Position| position.index(sample_rate, channels).min(samples.len());
+
+        let (loop_start, loop_end) = match loops {
+            Some(loops) => (
+                to_index(loops.start),
+                loops.end.map_or(samples.len(), to_index),
+            ),
+            None => (0, samples.len()),
+        };
+
+        // A body that's empty or ends before it starts loops the whole track
+        let (loop_start, loop_end) = if loop_start < loop_end {
+            (loop_start, loop_end)
+        } else {
+            (0, samples.len())
+        };
+
+        Ok(Track {
+            samples,
+            channels,
+            sample_rate,
+            loop_start,
+            loop_end,
+        })
+    }
+}
+
+#[cfg(feature = "at3")]
+fn decode_at3(bytes: Vec<u8>) -> Result<(u16, u32, Vec<i16>), String> {
+    let decoder = atrac3p_decoder::Decoder::new(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+
+    Ok(collect(decoder))
+}
+
+#[cfg(not(feature = "at3"))]
+fn decode_at3(_bytes: Vec<u8>) -> Result<(u16, u32, Vec<i16>), String> {
+    Err("built without the `at3` feature".to_string())
+}
+
+fn collect<S: Source<Item = i16>>(source: S) -> (u16, u32, Vec<i16>) {
+    let channels = source.channels();
+    let sample_rate = source.sample_rate();
+
+    (channels, sample_rate, source.collect())
+}
+
+/// The first loop of the `smpl` chunk of a RIFF file, such as `.wav` or
+/// `.at3`.
+fn riff_loop(bytes: &[u8]) -> Option<LoopPoints> {
+    let u32_at = |offset: usize| {
+        bytes
+            .get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+
+    if bytes.get(0..4)? != b"RIFF" || bytes.get(8..12)? != b"WAVE" {
+        return None;
+    }
+
+    let mut offset = 12;
+
+    while let Some(id) = bytes.get(offset..offset + 4) {
+        let size = u32_at(offset + 4)? as usize;
+        let data = offset + 8;
+
+        // The loop count is followed by the sampler data length, then each
+        // 24 byte loop: cue id, type, start, end, fraction and play count
+        if id == b"smpl" && u32_at(data + 28)? > 0 {
+            let start = u32_at(data + 36 + 8)?;
+            let end = u32_at(data + 36 + 12)?;
+
+            return Some(LoopPoints {
+                start: Position::Samples(u64::from(start)),
+                // The end sample is part of the loop
+                end: Some(Position::Samples(u64::from(end) + 1)),
+            });
+        }
+
+        // Chunks are padded to an even length
+        offset = data + size + (size & 1);
+    }
+
+    None
+}
+
+/// Plays a track from the start, then loops its body forever.
+struct LoopingSource {
+    track: Arc<Track>,
+    position: usize,
+}
+
+impl Iterator for LoopingSource {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        if self.position >= self.track.loop_end {
+            self.position = self.track.loop_start;
+        }
+
+        let sample = self.track.samples.get(self.position).copied();
+        self.position += 1;
+
+        sample
+    }
+}
+
+impl Source for LoopingSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.track.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.track.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Background music. Only the tracks switched to are decoded, on the thread
+/// pool, and the current one starts playing once it's decoded.
+#[derive(Default)]
+pub struct Music {
+    loops: LoopTable,
+    /// Tracks done decoding, kept until another track is switched to.
+    decoded: Arc<Mutex<HashMap<Audio, Result<Arc<Track>, String>>>>,
+    /// Track last sent to the thread pool.
+    decoding: Option<Audio>,
+    /// Track of the current map, silence if `None`.
+    current: Option<Audio>,
+    /// Track last switched to, which `sink` plays unless it failed to decode.
+    playing: Option<Audio>,
+    sink: Option<Sink>,
+}
+
+impl Music {
+    pub fn new(loops: LoopTable) -> Self {
+        Music {
+            loops,
+            ..Music::default()
+        }
+    }
+
+    /// Switches to `audio`, a path relative to `assets_dir`. Unless it's
+    /// already decoded or decoding, it's decoded on `pool` and reported to
+    /// `progress` once done or failed. The track carries on if it's already
+    /// playing, and music stops if `audio` is `None`.
+    pub fn play<P: Progress>(
+        &mut self,
+        audio: Option<Audio>,
+        assets_dir: &Path,
+        pool: &ArcThreadPool,
+        mut progress: P,
+    ) {
+        self.current = audio.clone();
+
+        let audio = match audio {
+            Some(audio) => audio,
+            None => return,
+        };
+
+        {
+            let mut decoded = lock(&self.decoded);
+
+            // Decoded tracks are large, so only the one switched to is kept
+            decoded.retain(|other, _| *other == audio);
+
+            if decoded.contains_key(&audio) || self.decoding.as_ref() == Some(&audio) {
+                return;
+            }
+        }
+
+        self.decoding = Some(audio.clone());
+
+        let path = assets_dir.join(&audio.0);
+        let loops = self.loops.tracks.get(&audio).copied();
+        let decoded = self.decoded.clone();
+
+        progress.add_assets(1);
+        let tracker = Box::new(progress.create_tracker());
+
+        pool.spawn(move || {
+            let track = Track::open(&path, loops).map(Arc::new);
+
+            match &track {
+                Ok(_) => tracker.success(),
+                // Tracks have no asset handle to report
+                Err(e) => tracker.fail(
+                    0,
+                    "Track",
+                    path.display().to_string(),
+                    amethyst::Error::from_string(e.clone()),
+                ),
+            }
+
+            lock(&decoded).insert(audio, track);
+        });
+    }
+
+    /// Switches to `current` once it's decoded, carrying on if it's already
+    /// playing.
+    pub fn update(&mut self) {
+        if self.playing == self.current {
+            return;
+        }
+
+        // Stop the previous track right away, not once the next one is decoded
+        self.sink = None;
+        self.playing = None;
+
+        let audio = match &self.current {
+            Some(audio) => audio,
+            None => return,
+        };
+
+        let track = match lock(&self.decoded).get(audio) {
+            Some(Ok(track)) => track.clone(),
+            // The progress counter has reported the error
+            Some(Err(_)) => {
+                self.playing = self.current.clone();
+                return;
+            }
+            // Still decoding
+            None => return,
+        };
+
+        let device = match rodio::default_output_device() {
+            Some(device) => device,
+            None => {
+                error!("No audio output device to play music on");
+
+                self.playing = self.current.clone();
+                return;
+            }
+        };
+
+        let sink = Sink::new(&device);
+        sink.append(LoopingSource { track, position: 0 });
+
+        self.sink = Some(sink);
+        self.playing = self.current.clone();
+    }
+}
+
+/// Locks the decoded tracks. A decode that panics never holds the lock, so
+/// the map is whole even if the lock is poisoned.
+fn lock(
+    decoded: &Mutex<HashMap<Audio, Result<Arc<Track>, String>>>,
+) -> MutexGuard<'_, HashMap<Audio, Result<Arc<Track>, String>>> {
+    decoded.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{riff_loop, AudioFormat, LoopPoints, Position};
+
+    /// A RIFF WAVE file with a `smpl` chunk looping `start..=end`, after an
+    /// odd sized chunk to check the padding is skipped.
+    fn wav_with_loop(start: u32, end: u32) -> Vec<u8> {
+        let mut smpl = vec![0; 36];
+        // Loop count
+        smpl[28..32].copy_from_slice(&1u32.to_le_bytes());
+
+        let mut sample_loop = vec![0; 24];
+        sample_loop[8..12].copy_from_slice(&start.to_le_bytes());
+        sample_loop[12..16].copy_from_slice(&end.to_le_bytes());
+        smpl.extend(sample_loop);
+
+        let mut bytes = b"RIFF\0\0\0\0WAVE".to_vec();
+
+        bytes.extend(b"junk");
+        bytes.extend(&3u32.to_le_bytes());
+        bytes.extend(&[1, 2, 3, 0]);
+
+        bytes.extend(b"smpl");
+        bytes.extend(&(smpl.len() as u32).to_le_bytes());
+        bytes.extend(smpl);
+
+        let size = (bytes.len() - 8) as u32;
+        bytes[4..8].copy_from_slice(&size.to_le_bytes());
+
+        bytes
+    }
+
+    #[test]
+    fn reads_the_first_smpl_loop() {
+        assert_eq!(
+            riff_loop(&wav_with_loop(1000, 4999)),
+            Some(LoopPoints {
+                start: Position::Samples(1000),
+                end: Some(Position::Samples(5000)),
+            })
+        );
+    }
+
+    #[test]
+    fn ignores_files_without_loops() {
+        let mut no_loops = wav_with_loop(1000, 4999);
+        // Loop count of the `smpl` chunk
+        let count = no_loops.len() - 60 + 28;
+        no_loops[count..count + 4].copy_from_slice(&0u32.to_le_bytes());
+
+        assert_eq!(riff_loop(&no_loops), None);
+        assert_eq!(riff_loop(b"OggS"), None);
+
+        let truncated = wav_with_loop(1000, 4999);
+        assert_eq!(riff_loop(&truncated[..truncated.len() - 10]), None);
+    }
+
+    #[test]
+    fn converts_positions_to_interleaved_indices() {
+        assert_eq!(Position::Seconds(0.5).frame(44100), 22050);
+        assert_eq!(Position::Seconds(0.5).index(44100, 2), 44100);
+        assert_eq!(Position::Samples(300).index(48000, 2), 600);
+        assert_eq!(Position::Samples(300).index(48000, 0), 300);
+    }
+
+    #[test]
+    fn picks_formats_by_extension() {
+        let format = |path: &str| AudioFormat::from_path(Path::new(path));
+
+        assert_eq!(format("audio/bgm/prologue.at3"), Some(AudioFormat::At3));
+        assert_eq!(format("audio/bgm/prologue.OGG"), Some(AudioFormat::Ogg));
+        assert_eq!(format("fan/track.Flac"), Some(AudioFormat::Flac));
+        assert_eq!(format("track.wav"), Some(AudioFormat::Wav));
+        assert_eq!(format("track.mp3"), Some(AudioFormat::Mp3));
+        assert_eq!(format("track.mid"), None);
+        assert_eq!(format("track"), None);
+    }
+}
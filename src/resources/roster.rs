@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashSet;
+
+/// The characters the player can switch between, read from
+/// `assets/characters.ron`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Roster {
+    pub characters: Vec<CharacterEntry>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CharacterEntry {
+    /// Unique id the character's prefab is stored under in `PrefabList`.
+    pub id: String,
+    pub name: String,
+    /// Path of the animation prefab, relative to the assets directory.
+    pub prefab: String,
+    /// Path of the portrait image, relative to the assets directory.
+    #[serde(default)]
+    pub portrait: Option<String>,
+    /// Pixels moved per fixed update while running. Must divide the 32px of a
+    /// tile, so every step ends on a tile.
+    #[serde(default = "default_speed")]
+    pub speed: u8,
+}
+
+fn default_speed() -> u8 {
+    4
+}
+
+impl Roster {
+    /// Checks there's at least one character, ids are unique and speeds
+    /// divide a tile.
+    pub fn check(&self) -> Result<(), String> {
+        if self.characters.is_empty() {
+            return Err("The roster doesn't list any characters".to_string());
+        }
+
+        let mut ids = HashSet::new();
+
+        for character in self.characters.iter() {
+            if !ids.insert(character.id.as_str()) {
+                return Err(format!("Character id `{}` is used twice", character.id));
+            }
+
+            if !matches!(character.speed, 1 | 2 | 4 | 8 | 16 | 32) {
+                return Err(format!(
+                    "Speed {} of `{}` doesn't divide the 32px of a tile",
+                    character.speed, character.id
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
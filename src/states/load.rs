@@ -1,11 +1,12 @@
 use amethyst::winit::{Event, WindowEvent};
 use amethyst::{
-    assets::{AssetStorage, Handle, Loader, ProgressCounter, RonFormat},
-    audio::{output::Output, AudioSink},
+    assets::{AssetStorage, Handle, ProgressCounter},
     core::{ArcThreadPool, Time},
     ecs::{Dispatcher, DispatcherBuilder},
     prelude::{GameData, SimpleState, SimpleTrans, StateData, StateEvent, Trans, World, WorldExt},
 };
+use cn2::TextureKind;
+use log::{error, warn};
 
 use std::time::Duration;
 
@@ -14,9 +15,10 @@ use crate::{
         camera::load_camera, camera_subject::load_camera_subject, player_one::load_player_one,
     },
     resources::{
-        asset::{load_assets, PrefabList},
+        asset::{load_assets, AssetType, AssetsDir, PrefabList},
         game::Game,
-        map::{Map, MapSpriteSheets, TextureKind},
+        map::{get_map_handle, Map, MapSpriteSheets},
+        music::Music,
         sprites::get_sprite_sheet_handle,
     },
     systems,
@@ -95,8 +97,7 @@ impl<'a, 'b> SimpleState for LoadState<'a, 'b> {
 
             {
                 let game = world.read_resource::<Game>();
-                assets.extend_from_slice(&game.chars);
-                assets.extend_from_slice(&game.music);
+                assets.extend(game.chars.iter().cloned().map(AssetType::Character));
             }
 
             Some(load_assets(world, &assets))
@@ -108,19 +109,17 @@ impl<'a, 'b> SimpleState for LoadState<'a, 'b> {
             let mut game = world.write_resource::<Game>();
 
             let map_idx = game.load_map.take().unwrap_or(0);
-            let map_name = game.maps[map_idx];
-
             game.current_map = map_idx;
 
-            let loader = world.read_resource::<Loader>();
-            Some(loader.load(
-                map_name,
-                RonFormat,
+            Some(get_map_handle(
+                world,
+                &game.map_entry().path,
                 self.progress_counter.as_mut().expect("map"),
-                &world.read_resource::<AssetStorage<Map>>(),
             ))
         };
 
+        play_map_music(world, self.progress_counter.as_mut().expect("music"));
+
         let subject = load_camera_subject(world);
         load_camera(world, subject);
     }
@@ -136,36 +135,33 @@ impl<'a, 'b> SimpleState for LoadState<'a, 'b> {
                     map_storage.get(map_handle).unwrap().clone()
                 };
 
-                {
-                    let mut game = data.world.write_resource::<Game>();
-                    game.map_width = map.width as f32 * 32.0;
-                    game.map_height = map.height as f32 * 32.0;
-                }
-
-                let mut map_sheets = MapSpriteSheets::default();
-
-                let base_sheet = get_sprite_sheet_handle(&data.world, &map.base, 16, 16, (32, 32));
-                let var_sheet = get_sprite_sheet_handle(&data.world, &map.var, 16, 16, (32, 32));
-                let anm_sheet = get_sprite_sheet_handle(&data.world, &map.anm, 16, 16, (32, 32));
-
-                map_sheets.insert(TextureKind::Base, base_sheet);
-                map_sheets.insert(TextureKind::Var, var_sheet);
-                map_sheets.insert(TextureKind::Anm, anm_sheet);
+                let missing_textures = {
+                    let assets_dir = data.world.read_resource::<AssetsDir>();
+                    map.missing_textures(&assets_dir.0)
+                };
 
-                data.world.insert(map_sheets);
+                if missing_textures.is_empty() {
+                    {
+                        let assets_dir = data.world.read_resource::<AssetsDir>();
+                        let game = data.world.read_resource::<Game>();
 
-                map.load_map(data.world);
+                        for problem in map.validate(&assets_dir.0).iter() {
+                            warn!("Map {}: {}", game.map_entry().path, problem);
+                        }
+                    }
 
-                let player_one_prefab_handle = {
+                    show_map(data.world, &map);
+                } else {
                     let game = data.world.read_resource::<Game>();
-                    let prefab_list = data.world.read_resource::<PrefabList>();
-                    prefab_list
-                        .get(game.chars[game.current_char])
-                        .unwrap()
-                        .clone()
-                };
 
-                load_player_one(data.world, &map, player_one_prefab_handle);
+                    for path in missing_textures.iter() {
+                        error!(
+                            "Can't show map {}, texture {} is missing",
+                            game.map_entry().path,
+                            path.display()
+                        );
+                    }
+                }
 
                 self.progress_counter = None;
             }
@@ -179,8 +175,6 @@ impl<'a, 'b> SimpleState for LoadState<'a, 'b> {
         };
 
         if load_map.is_some() {
-            stop_music(&mut data.world);
-
             Trans::Replace(Box::new(LoadState::default()))
         } else {
             Trans::None
@@ -216,13 +210,50 @@ impl<'a, 'b> SimpleState for LoadState<'a, 'b> {
     }
 }
 
-fn stop_music(world: &mut World) {
-    let sink = if let Some(ref output) = world.try_fetch::<Output>() {
-        Some(AudioSink::new(output))
-    } else {
-        None
-    };
-    if let Some(sink) = sink {
-        world.insert(sink);
+/// Spawns the tiles of `map` and the player.
+fn show_map(world: &mut World, map: &Map) {
+    {
+        let mut game = world.write_resource::<Game>();
+        game.map_width = map.width as f32 * 32.0;
+        game.map_height = map.height as f32 * 32.0;
     }
+
+    let mut map_sheets = MapSpriteSheets::default();
+
+    let base_sheet = get_sprite_sheet_handle(world, &map.base, 16, 16, (32, 32));
+    let var_sheet = get_sprite_sheet_handle(world, &map.var, 16, 16, (32, 32));
+    let anm_sheet = get_sprite_sheet_handle(world, &map.anm, 16, 16, (32, 32));
+
+    map_sheets.insert(TextureKind::Base, base_sheet);
+    map_sheets.insert(TextureKind::Var, var_sheet);
+    map_sheets.insert(TextureKind::Anm, anm_sheet);
+
+    world.insert(map_sheets);
+
+    map.load_map(world);
+
+    let (player_one_prefab_handle, spawn, speed) = {
+        let game = world.read_resource::<Game>();
+        let prefab_list = world.read_resource::<PrefabList>();
+        (
+            prefab_list.get(&game.character().id).unwrap().clone(),
+            game.map_entry().spawn,
+            game.character().speed,
+        )
+    };
+
+    load_player_one(world, map, spawn, speed, player_one_prefab_handle);
+}
+
+/// Switches to the BGM of the current map, decoding it on the thread pool
+/// unless it's already decoded. The track carries on if it's already playing,
+/// and music stops if the map has none.
+fn play_map_music(world: &mut World, progress_counter: &mut ProgressCounter) {
+    let bgm = world.read_resource::<Game>().map_entry().bgm.clone();
+    let assets_dir = world.read_resource::<AssetsDir>();
+    let pool = world.read_resource::<ArcThreadPool>();
+
+    world
+        .write_resource::<Music>()
+        .play(bgm, &assets_dir.0, &pool, progress_counter);
 }
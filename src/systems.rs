@@ -13,3 +13,6 @@ pub use transformation::{CameraTransformationSystem, PlayerOneTransformationSyst
 
 mod debug;
 pub use debug::DebugSystem;
+
+mod music;
+pub use music::MusicSystem;
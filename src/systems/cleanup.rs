@@ -1,7 +1,9 @@
 use amethyst::{
     core::Transform,
     derive::SystemDesc,
-    ecs::{Entities, Join, LazyUpdate, Read, ReadStorage, System, SystemData, Write},
+    ecs::{
+        Entities, Join, LazyUpdate, Read, ReadExpect, ReadStorage, System, SystemData, WriteExpect,
+    },
     utils::removal::{exec_removal, Removal},
 };
 
@@ -16,7 +18,7 @@ impl<'s> System<'s> for CleanupSystem {
     type SystemData = (
         ReadStorage<'s, Removal<usize>>,
         Entities<'s>,
-        Read<'s, Game>,
+        ReadExpect<'s, Game>,
     );
 
     fn run(&mut self, (removals, entities, game): Self::SystemData) {
@@ -32,7 +34,7 @@ pub struct PlayerOneReloadSystem;
 impl<'s> System<'s> for PlayerOneReloadSystem {
     type SystemData = (
         Entities<'s>,
-        Write<'s, Game>,
+        WriteExpect<'s, Game>,
         Read<'s, PrefabList>,
         ReadStorage<'s, PlayerOne>,
         ReadStorage<'s, Transform>,
@@ -49,12 +51,8 @@ impl<'s> System<'s> for PlayerOneReloadSystem {
 
                 let _ = entities.delete(entity);
 
-                let player_one_prefab_handle = {
-                    prefab_list
-                        .get(game.chars[game.current_char])
-                        .unwrap()
-                        .clone()
-                };
+                let player_one_prefab_handle =
+                    prefab_list.get(&game.character().id).unwrap().clone();
 
                 let player_one = entities.create();
 
@@ -63,6 +61,7 @@ impl<'s> System<'s> for PlayerOneReloadSystem {
                     player_one,
                     current_pos.0,
                     current_pos.1,
+                    game.character().speed,
                     player_one_prefab_handle,
                 );
 
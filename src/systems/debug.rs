@@ -1,12 +1,12 @@
 use amethyst::{
     derive::SystemDesc,
-    ecs::{Entities, Join, Read, ReadStorage, System, SystemData, Write, WriteStorage},
+    ecs::{Entities, Join, Read, ReadStorage, System, SystemData, WriteExpect, WriteStorage},
     input::{InputHandler, StringBindings},
     renderer::resources::Tint,
 };
 
 use crate::{
-    components::map::{LowerTile, TriggerKind, UpperTile},
+    components::map::{LowerTile, Trigger, UpperTile},
     resources::game::{DebugMode, Game},
 };
 
@@ -18,10 +18,10 @@ impl<'s> System<'s> for DebugSystem {
         Entities<'s>,
         WriteStorage<'s, Tint>,
         Read<'s, InputHandler<StringBindings>>,
-        Write<'s, Game>,
+        WriteExpect<'s, Game>,
         ReadStorage<'s, UpperTile>,
         ReadStorage<'s, LowerTile>,
-        ReadStorage<'s, TriggerKind>,
+        ReadStorage<'s, Trigger>,
     );
 
     fn run(
@@ -3,15 +3,16 @@ use amethyst::{
     derive::SystemDesc,
     ecs::{
         shred::Fetch, storage::MaskedStorage, Join, Read, ReadStorage, Storage, System, SystemData,
-        Write, WriteStorage,
+        WriteExpect, WriteStorage,
     },
     input::{InputHandler, StringBindings},
 };
+use cn2::TriggerKind;
 
 use crate::{
     components::{
         direction::{Direction, Directions},
-        map::TriggerKind,
+        map::{LowerTile, Trigger},
         movement::Movement,
         player_one::{PlayerOne, PlayerOneState},
     },
@@ -22,7 +23,10 @@ use crate::{
 pub struct GeneralInputSystem;
 
 impl<'s> System<'s> for GeneralInputSystem {
-    type SystemData = (Read<'s, InputHandler<StringBindings>>, Write<'s, Game>);
+    type SystemData = (
+        Read<'s, InputHandler<StringBindings>>,
+        WriteExpect<'s, Game>,
+    );
 
     fn run(&mut self, (input, mut game): Self::SystemData) {
         if !game.button_pressed {
@@ -81,12 +85,21 @@ impl<'s> System<'s> for PlayerOneInputSystem {
         WriteStorage<'s, Direction>,
         WriteStorage<'s, Movement>,
         ReadStorage<'s, Transform>,
-        ReadStorage<'s, TriggerKind>,
+        ReadStorage<'s, Trigger>,
+        ReadStorage<'s, LowerTile>,
     );
 
     fn run(
         &mut self,
-        (input, mut player_one, mut directions, mut movements, transforms, triggers): Self::SystemData,
+        (
+            input,
+            mut player_one,
+            mut directions,
+            mut movements,
+            transforms,
+            triggers,
+            lower_tiles,
+        ): Self::SystemData,
     ) {
         for (player_one, direction, movement, player_transform) in (
             &mut player_one,
@@ -132,7 +145,15 @@ impl<'s> System<'s> for PlayerOneInputSystem {
                     //     player_one.state = PlayerOneState::Running;
                     // }
 
-                    if is_blocker(player_transform, new_direction, &triggers, &transforms) {
+                    let trigger = destination_trigger(
+                        player_transform,
+                        new_direction,
+                        &triggers,
+                        &transforms,
+                        &lower_tiles,
+                    );
+
+                    if matches!(trigger, None | Some(TriggerKind::Blocker)) {
                         player_one.state = PlayerOneState::Idle;
                     } else {
                         player_one.state = PlayerOneState::Running;
@@ -146,12 +167,17 @@ impl<'s> System<'s> for PlayerOneInputSystem {
     }
 }
 
-fn is_blocker(
+/// Returns the lower layer's trigger on the tile the player would move onto,
+/// `None` if the move leaves the map. Collision uses the lower trigger table,
+/// the one the game read before the upper table was decoded, as it's not yet
+/// known how the upper table affects movement.
+fn destination_trigger(
     player_transform: &Transform,
     new_direction: Directions,
-    triggers: &Storage<TriggerKind, Fetch<MaskedStorage<TriggerKind>>>,
+    triggers: &Storage<Trigger, Fetch<MaskedStorage<Trigger>>>,
     transforms: &Storage<Transform, Fetch<MaskedStorage<Transform>>>,
-) -> bool {
+    lower_tiles: &Storage<LowerTile, Fetch<MaskedStorage<LowerTile>>>,
+) -> Option<TriggerKind> {
     let new_position = match new_direction {
         Directions::North => (
             player_transform.translation().x,
@@ -171,31 +197,28 @@ fn is_blocker(
         ),
     };
 
-    let mut inside_map = false;
-
-    for (trigger, tile_transform) in (triggers, transforms).join() {
+    for (trigger, tile_transform, _) in (triggers, transforms, lower_tiles).join() {
         let tile_position = (
             tile_transform.translation().x,
             tile_transform.translation().y,
         );
 
         if tile_position == new_position {
-            inside_map = true;
-
-            if trigger == &TriggerKind::Blocker {
-                return true;
-            }
+            return Some(trigger.0);
         }
     }
 
-    !inside_map
+    None
 }
 
 #[derive(SystemDesc)]
 pub struct KeyReleaseSystem;
 
 impl<'s> System<'s> for KeyReleaseSystem {
-    type SystemData = (Read<'s, InputHandler<StringBindings>>, Write<'s, Game>);
+    type SystemData = (
+        Read<'s, InputHandler<StringBindings>>,
+        WriteExpect<'s, Game>,
+    );
 
     fn run(&mut self, (input, mut game): Self::SystemData) {
         if input.keys_that_are_down().peekable().peek().is_none() {
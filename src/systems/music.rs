@@ -0,0 +1,14 @@
+use amethyst::ecs::{System, Write};
+
+use crate::resources::music::Music;
+
+/// Switches the BGM once the track of the current map is decoded.
+pub struct MusicSystem;
+
+impl<'s> System<'s> for MusicSystem {
+    type SystemData = Write<'s, Music>;
+
+    fn run(&mut self, mut music: Self::SystemData) {
+        music.update();
+    }
+}
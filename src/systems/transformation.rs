@@ -1,6 +1,6 @@
 use amethyst::{
     core::Transform,
-    ecs::{Join, Read, ReadStorage, System, WriteStorage},
+    ecs::{Join, ReadExpect, ReadStorage, System, WriteStorage},
 };
 
 use crate::{
@@ -28,7 +28,7 @@ impl<'s> System<'s> for PlayerOneTransformationSystem {
             (&player_one, &directions, &mut transforms, &mut movements).join()
         {
             if player.state == PlayerOneState::Running {
-                let run_amount = 4.0;
+                let run_amount = f32::from(movement.speed);
 
                 match direction.current {
                     Directions::North => {
@@ -45,7 +45,7 @@ impl<'s> System<'s> for PlayerOneTransformationSystem {
                     }
                 }
 
-                movement.run_count = (movement.run_count + 1) % 8;
+                movement.run_count = (movement.run_count + 1) % movement.steps();
             }
 
             if ((direction.current == Directions::East && direction.previous != Directions::East)
@@ -67,7 +67,7 @@ impl<'s> System<'s> for CameraTransformationSystem {
         ReadStorage<'s, PlayerOne>,
         ReadStorage<'s, Subject>,
         WriteStorage<'s, Transform>,
-        Read<'s, Game>,
+        ReadExpect<'s, Game>,
     );
 
     fn run(&mut self, (player_one, subjects, mut transforms, game): Self::SystemData) {
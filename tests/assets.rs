@@ -0,0 +1,46 @@
+//! Runs the map tools over the maps shipped in `assets/maps`.
+
+use std::{
+    path::Path,
+    process::{Command, Output},
+};
+
+fn run(tool: &str, args: &[&str]) -> Output {
+    Command::new(tool)
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn every_file_in_assets_maps_is_a_map() {
+    let stats = run(env!("CARGO_BIN_EXE_map-stats"), &["assets/maps"]);
+
+    assert!(
+        stats.status.success(),
+        "{}",
+        String::from_utf8_lossy(&stats.stderr)
+    );
+}
+
+#[test]
+fn assets_maps_validate() {
+    let validate = run(env!("CARGO_BIN_EXE_map-validate"), &["assets/maps", "-q"]);
+    let stderr = String::from_utf8_lossy(&validate.stderr);
+
+    assert!(!stderr.contains("Failed "), "{}", stderr);
+
+    // The tilesets are extracted from the game rather than checked in, so the
+    // textures can only be checked where they've been extracted
+    if Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("assets/tilesets")
+        .is_dir()
+    {
+        assert!(
+            validate.status.success(),
+            "{}",
+            String::from_utf8_lossy(&validate.stdout)
+        );
+    }
+}